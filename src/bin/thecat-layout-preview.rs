@@ -0,0 +1,266 @@
+//! Previews the cat's layouts without an X server, so they can be tuned without restarting the
+//! WM.  It prints the tiles as an ASCII grid, along with each one's aspect ratio and the layout's
+//! cost (lower is better, see the cost module), and can draw them as an SVG as well.
+//!
+//!     thecat-layout-preview 1920x1080 focus 5
+//!     thecat-layout-preview 3440x1440 fair 7 1 0.5 --svg fair.svg
+//!
+
+#![forbid(unsafe_code)]
+
+use std::{env, fmt::Write, fs, process};
+
+use penrose::{
+    client::Client,
+    data_types::{Region, WinId},
+};
+
+use thecat::layouts::{self, aspect_ratio, DefaultLayout, Target};
+
+const USAGE: &str = "usage: thecat-layout-preview WIDTHxHEIGHT LAYOUT CLIENTS [MAX_MAIN [RATIO]] \
+                     [--focus N] [--svg FILE]";
+
+/// How wide the ASCII grid is, in characters.
+const GRID_WIDTH: u32 = 100;
+
+/// The SVG attributes for drawing the tiles.
+const TILE_STYLE: &str = r##"fill="#4a6" stroke="#111" stroke-width="2""##;
+/// The SVG attributes for drawing the labels.
+const TEXT_STYLE: &str = r##"font-family="monospace" fill="#fff""##;
+
+/// What to preview, from the command line.
+struct Args {
+    screen: Region,
+    layout: String,
+    clients: u32,
+    max_main: Option<u32>,
+    ratio: Option<f32>,
+    focus: u32,
+    svg: Option<String>,
+}
+
+fn parse_args() -> Option<Args> {
+    let mut positional = Vec::new();
+    let (mut focus, mut svg) = (0, None);
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--focus" => focus = args.next()?.parse().ok()?,
+            "--svg" => svg = Some(args.next()?),
+            _ => positional.push(arg),
+        }
+    }
+    if !(3..=5).contains(&positional.len()) {
+        return None;
+    }
+
+    let mut size = positional[0].split('x').map(|n| n.parse::<u32>());
+    let (w, h) = match (size.next(), size.next(), size.next()) {
+        (Some(Ok(w)), Some(Ok(h)), None) if w > 0 && h > 0 => (w, h),
+        _ => return None,
+    };
+    Some(Args {
+        screen: Region::new(0, 0, w, h),
+        layout: positional[1].clone(),
+        clients: positional[2].parse().ok()?,
+        max_main: match positional.get(3) {
+            Some(n) => Some(n.parse().ok()?),
+            None => None,
+        },
+        ratio: match positional.get(4) {
+            Some(r) => Some(r.parse().ok()?),
+            None => None,
+        },
+        focus,
+        svg,
+    })
+}
+
+/// A window's place in the preview.
+struct Tile {
+    id: WinId,
+    region: Option<Region>,
+    target: f32,
+}
+
+fn main() {
+    let args = parse_args().unwrap_or_else(|| fail(USAGE));
+    let previews = layouts::default_layouts();
+    let preview = previews.iter().find(|p| name(p) == args.layout).unwrap_or_else(|| {
+        let names: Vec<&str> = previews.iter().map(name).collect();
+        fail(&format!("unknown layout {}, try one of: {}", args.layout, names.join(", ")))
+    });
+
+    let clients: Vec<Client> = (0..args.clients)
+        .map(|id| Client::new(id, "preview".to_string(), "preview".to_string(), 0, false))
+        .collect();
+    let clients: Vec<&Client> = clients.iter().collect();
+    let targets = (preview.targets)(&clients);
+    let actions = (preview.func)(
+        &clients,
+        Some(args.focus).filter(|id| *id < args.clients),
+        &args.screen,
+        args.max_main.unwrap_or(preview.max_main),
+        args.ratio.unwrap_or(preview.ratio),
+    );
+    let tiles: Vec<Tile> = actions
+        .into_iter()
+        .map(|(id, region)| Tile { id, region, target: targets[id as usize].aspect_ratio })
+        .collect();
+
+    // Only the windows on screen count towards the cost.
+    let (shown, shown_targets): (Vec<Region>, Vec<Target>) = tiles
+        .iter()
+        .filter_map(|t| {
+            t.region.filter(|r| on_screen(&args.screen, r)).map(|r| (r, targets[t.id as usize]))
+        })
+        .unzip();
+    let cost = preview.cost.cost(&shown, &shown_targets);
+
+    print!("{}", ascii_grid(&args.screen, &tiles));
+    for tile in &tiles {
+        match tile.region {
+            Some(r) => {
+                let (x, y, w, h) = r.values();
+                println!(
+                    "{:>3}: {}x{}+{}+{}  aspect ratio {:.2} (target {:.2})",
+                    tile.id,
+                    w,
+                    h,
+                    x,
+                    y,
+                    aspect_ratio(&r),
+                    tile.target
+                );
+            }
+            None => println!("{:>3}: hidden", tile.id),
+        }
+    }
+    println!("cost: {:.4}", cost);
+
+    if let Some(path) = args.svg {
+        if let Err(e) = fs::write(&path, svg(&args.screen, &tiles, cost)) {
+            fail(&format!("unable to write {}: {}", path, e));
+        }
+    }
+}
+
+/// What the layout is called on the command line: its symbol, without the brackets.
+fn name(layout: &DefaultLayout) -> &'static str {
+    layout.layout.symbol.trim_matches(|c| c == '[' || c == ']')
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
+
+/// Whether any of the region is on the screen.
+fn on_screen(screen: &Region, r: &Region) -> bool {
+    let (sx, sy, sw, sh) = screen.values();
+    let (x, y, _, _) = r.values();
+    x < sx + sw && y < sy + sh
+}
+
+/// Draws the outline of each tile, scaled down to fit the grid.  Characters are roughly twice as
+/// tall as they are wide, so the grid has half as many rows as it would need for square cells.
+fn ascii_grid(screen: &Region, tiles: &[Tile]) -> String {
+    let (sx, sy, sw, sh) = screen.values();
+    let cols = GRID_WIDTH as usize;
+    let rows = ((GRID_WIDTH * sh) as f32 / sw as f32 / 2.0).ceil().max(1.0) as usize;
+    // Windows partly off screen are cut off at the edge of the grid.
+    let col =
+        |x: u32| (((x.saturating_sub(sx) as f32 / sw as f32) * cols as f32) as usize).min(cols);
+    let row =
+        |y: u32| (((y.saturating_sub(sy) as f32 / sh as f32) * rows as f32) as usize).min(rows);
+
+    let mut grid = vec![vec![' '; cols + 1]; rows + 1];
+    for tile in tiles {
+        let (x, y, w, h) = match tile.region {
+            // Some layouts (eg scrolling) park windows off screen.
+            Some(r) if on_screen(screen, &r) => r.values(),
+            _ => continue,
+        };
+        // Neighbouring tiles share their edges, so don't draw over the corners of the others.
+        let (left, right) = (col(x), col(x + w));
+        let (top, bottom) = (row(y), row(y + h));
+        let edge = |c: &mut char, ch: char| *c = if *c == '+' { '+' } else { ch };
+        for line in &mut [top, bottom] {
+            grid[*line][left..=right].iter_mut().for_each(|c| edge(c, '-'));
+        }
+        for line in grid.iter_mut().take(bottom + 1).skip(top) {
+            edge(&mut line[left], '|');
+            edge(&mut line[right], '|');
+        }
+        for &(r, c) in &[(top, left), (top, right), (bottom, left), (bottom, right)] {
+            grid[r][c] = '+';
+        }
+
+        // Label the tile inside its top left corner, if it fits.
+        if bottom > top + 1 {
+            let label = format!("{} {:.2}", tile.id, aspect_ratio(&Region::new(x, y, w, h)));
+            for (i, ch) in label.chars().take(right.saturating_sub(left + 1)).enumerate() {
+                grid[top + 1][left + 1 + i] = ch;
+            }
+        }
+    }
+
+    grid.iter().map(|line| line.iter().collect::<String>().trim_end().to_string() + "\n").collect()
+}
+
+fn svg(screen: &Region, tiles: &[Tile], cost: f32) -> String {
+    let (sx, sy, sw, sh) = screen.values();
+    // Scale the text with the screen, so it stays legible however big the screen is.
+    let font_size = (sw.min(sh) / 40).max(8);
+
+    let mut out = String::new();
+    // Writing to a String can't fail.
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+        sx,
+        sy,
+        sw,
+        sh,
+        sw / 2,
+        sh / 2
+    );
+    let _ = writeln!(
+        out,
+        r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#222"/>"##,
+        sx, sy, sw, sh
+    );
+    for tile in tiles {
+        let (x, y, w, h) = match tile.region {
+            Some(r) => r.values(),
+            None => continue,
+        };
+        let _ = writeln!(
+            out,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+            x, y, w, h, TILE_STYLE
+        );
+        let _ = writeln!(
+            out,
+            r#"<text x="{}" y="{}" font-size="{}" {}>{}: {:.2} (target {:.2})</text>"#,
+            x + font_size / 2,
+            y + font_size * 3 / 2,
+            font_size,
+            TEXT_STYLE,
+            tile.id,
+            aspect_ratio(&Region::new(x, y, w, h)),
+            tile.target
+        );
+    }
+    let _ = writeln!(
+        out,
+        r#"<text x="{}" y="{}" font-size="{}" {}>cost: {:.4}</text>"#,
+        sx + font_size / 2,
+        sy + sh - font_size / 2,
+        font_size,
+        TEXT_STYLE,
+        cost
+    );
+    out.push_str("</svg>\n");
+    out
+}
@@ -1,23 +1,677 @@
 //! Layouts are all of the fun and exciting ways the cat may barf windows onto your screen(s).
 //!
+//! Penrose layouts are plain function pointers, so they cannot capture any settings.  Instead
+//! each of the cat's layouts is generic over a type implementing `LayoutSettings`, for example:
+//!
+//! ```
+//! use thecat::layouts::{
+//!     cost::{AspectRatioSse, Cost, RowPenalty},
+//!     make_fair_layout, LayoutSettings, SizeHints,
+//! };
+//!
+//! struct Terminals;
+//! impl LayoutSettings for Terminals {
+//!     const TARGET_ASPECT_RATIO: f32 = 4.0 / 3.0;
+//!     const COST: &'static dyn Cost =
+//!         &RowPenalty { base: &AspectRatioSse, max_rows: 2, penalty: 0.5 };
+//!     const CLASS_ASPECT_RATIOS: &'static [(&'static str, f32)] = &[("mpv", 16.0 / 9.0)];
+//!     const CLASS_SIZE_HINTS: &'static [(&'static str, SizeHints)] = &[(
+//!         "Alacritty",
+//!         SizeHints { min_size: (400, 200), resize_increments: (9, 19), ..SizeHints::NONE },
+//!     )];
+//! }
+//!
+//! let layout = make_fair_layout::<Terminals>("[term]");
+//! ```
+//!
+//! The layout functions can also be mirrored, flipped, transposed or rotated, eg for a portrait
+//! monitor:
+//!
+//! ```
+//! use penrose::layout::{Layout, LayoutConf};
+//! use thecat::layouts::{fair_func, rotated_clockwise, Widescreen};
+//!
+//! let f = rotated_clockwise(fair_func::<Widescreen>())?;
+//! let layout = Layout::new("[rfair]", LayoutConf::default(), f, 1, 0.5);
+//! # Ok::<(), thecat::layouts::OutOfSlots>(())
+//! ```
+//!
+//! or split between two layouts, eg the central main layout for the first three windows on the
+//! left 60% of the screen and the fair layout for the rest on the right:
+//!
+//! ```
+//! use penrose::layout::{Layout, LayoutConf};
+//! use thecat::layouts::{
+//!     fair_func, horizontal_central_main_func, split, SplitClients, SplitDirection, Widescreen,
+//! };
+//!
+//! let f = split(
+//!     SplitDirection::LeftRight,
+//!     0.6,
+//!     SplitClients::First(3),
+//!     horizontal_central_main_func::<Widescreen>(),
+//!     fair_func::<Widescreen>(),
+//! )?;
+//! let layout = Layout::new("[split]", LayoutConf::default(), f, 1, 0.5);
+//! # Ok::<(), thecat::layouts::OutOfSlots>(())
+//! ```
+//!
+//! Only so many layout functions can be built this way (see `OutOfSlots`).
+//!
+
+use penrose::{
+    client::Client,
+    data_types::{Region, ResizeAction},
+    layout::{Layout, LayoutConf, LayoutFunc},
+};
 
-use penrose::layout::{Layout, LayoutConf};
+use cost::{AspectRatioSse, Cost};
 
+pub use dynamic::{remaining_slots, OutOfSlots};
+pub use utils::aspect_ratio;
+
+mod accordion;
+pub mod bsp;
+pub mod cost;
+pub mod description;
+mod dynamic;
 mod fair;
+mod guillotine;
 mod horizontal_central_main;
+pub mod main_region;
+pub mod overflow;
+pub mod screen_defaults;
+mod scrolling;
+pub mod size_hints;
+mod spiral;
+mod tabbed;
 mod utils;
+pub mod validation;
+mod vertical_central_main;
 
-/// This is the window aspect ratio that the tiling algorithm is trying to approximate.
+/// This is the window aspect ratio that the tiling algorithm tries to approximate by default.
 pub const TARGET_ASPECT_RATIO: f32 = 16.0 / 9.0;
 
+/// How tall the strip the tabbed layout leaves for its tabs is (in pixels).
+pub const TAB_STRIP_HEIGHT_PX: u32 = 18;
+
+/// The knobs that tune how a layout tiles its windows.  Every setting has a sensible default, so
+/// implementors need only override the ones they care about.
+pub trait LayoutSettings: 'static {
+    /// This is the window aspect ratio that the tiling algorithm is trying to approximate.
+    const TARGET_ASPECT_RATIO: f32 = TARGET_ASPECT_RATIO;
+
+    /// How much space (in pixels) to leave between neighboring windows.
+    const INNER_GAP_PX: u32 = 0;
+
+    /// How much space (in pixels) to leave between the windows and the edge of the screen.
+    const OUTER_GAP_PX: u32 = 0;
+
+    /// Should gaps be dropped when there is only one window to show?
+    const SMART_GAPS: bool = false;
+
+    /// The smallest (width, height) a fair layout will make a tile before stacking the windows that
+    /// don't fit (see the overflow module).  The default never stacks.
+    const MIN_TILE_SIZE: (u32, u32) = (0, 0);
+
+    /// Should each window be shrunk to exactly its aspect ratio, leaving the rest of its region
+    /// empty?  Handy for screen recording and video calls, where close isn't good enough.
+    const LETTERBOX: bool = false;
+
+    /// How to score the tilings a layout could use, the cheapest is the one used (see the cost
+    /// module).
+    const COST: &'static dyn Cost = &AspectRatioSse;
+
+    /// How the central main layouts share out the windows that don't fit in the main region.
+    const FILL_ORDER: FillOrder = FillOrder::LeftFirst;
+
+    /// Windows whose WM_CLASS appears here prefer the paired aspect ratio over the target one
+    /// (eg `("mpv", 16.0 / 9.0)` or `("Zathura", 8.5 / 11.0)`).
+    const CLASS_ASPECT_RATIOS: &'static [(&'static str, f32)] = &[];
+
+    /// Windows whose WM_CLASS appears here are kept within the paired size hints, rather than the
+    /// ones they set themselves (see the size_hints module), for apps whose hints are missing or
+    /// wrong.
+    const CLASS_SIZE_HINTS: &'static [(&'static str, SizeHints)] = &[];
+
+    /// Works out the (inner, outer) gaps to use when laying out the given number of windows.
+    fn gaps(window_count: usize) -> (u32, u32) {
+        if Self::SMART_GAPS && window_count <= 1 {
+            (0, 0)
+        } else {
+            (Self::INNER_GAP_PX, Self::OUTER_GAP_PX)
+        }
+    }
+
+    /// Looks up the aspect ratio windows of the given WM_CLASS would like to be.
+    fn aspect_ratio_for(wm_class: &str) -> f32 {
+        Self::CLASS_ASPECT_RATIOS
+            .iter()
+            .find(|(class, _)| *class == wm_class)
+            .map_or(Self::TARGET_ASPECT_RATIO, |(_, ratio)| *ratio)
+    }
+
+    /// Looks up the size hints for the given client: the ones for its WM_CLASS if there are any,
+    /// otherwise the ones it set itself.
+    fn size_hints_for(client: &Client) -> SizeHints {
+        Self::CLASS_SIZE_HINTS
+            .iter()
+            .find(|(class, _)| *class == client.wm_class())
+            .map(|(_, hints)| *hints)
+            .or_else(|| size_hints::hints_for(client.id()))
+            .unwrap_or(SizeHints::NONE)
+    }
+
+    /// Looks up what each of the given clients would like from its region.
+    fn targets_for(clients: &[&Client]) -> Vec<Target> {
+        clients
+            .iter()
+            .map(|c| {
+                let hints = Self::size_hints_for(c);
+                Target {
+                    aspect_ratio: hints.clamp_aspect_ratio(Self::aspect_ratio_for(c.wm_class())),
+                    min_size: hints.min_size,
+                }
+            })
+            .collect()
+    }
+
+    /// Shrinks the region each client was given to suit its window: letterboxed to the window's
+    /// exact aspect ratio (if `LETTERBOX` is set), then fit within its size hints.  Every layout
+    /// finishes with this, which is also when stale overflow stacks are forgotten.
+    fn fit_windows(clients: &[&Client], actions: Vec<ResizeAction>) -> Vec<ResizeAction> {
+        overflow::settle(clients);
+        let actions = if Self::LETTERBOX {
+            actions
+                .into_iter()
+                .map(|(id, region)| {
+                    let ratio = clients
+                        .iter()
+                        .find(|c| c.id() == id)
+                        .map_or(Self::TARGET_ASPECT_RATIO, |c| {
+                            Self::aspect_ratio_for(c.wm_class())
+                        });
+                    (id, region.map(|r| utils::letterbox(&r, ratio)))
+                })
+                .collect()
+        } else {
+            actions
+        };
+        Self::apply_size_hints(clients, actions)
+    }
+
+    /// Shrinks the region each client was given to fit within its size hints.
+    fn apply_size_hints(clients: &[&Client], actions: Vec<ResizeAction>) -> Vec<ResizeAction> {
+        actions
+            .into_iter()
+            .map(|(id, region)| {
+                let hints = clients
+                    .iter()
+                    .find(|c| c.id() == id)
+                    .map_or(SizeHints::NONE, |c| Self::size_hints_for(c));
+                (id, region.map(|r| hints.apply(&r)))
+            })
+            .collect()
+    }
+}
+
+/// What a window would like from the region it is given.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Target {
+    /// The aspect ratio the window would like to be.
+    pub aspect_ratio: f32,
+    /// The smallest (width, height) the window can sensibly be drawn at.
+    pub min_size: (u32, u32),
+}
+
+/// Size constraints for a window, as in the ICCCM's WM_NORMAL_HINTS.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SizeHints {
+    /// The smallest (width, height) the window can sensibly be drawn at.
+    pub min_size: (u32, u32),
+    /// The largest (width, height) the window can be, if it has a limit.
+    pub max_size: Option<(u32, u32)>,
+    /// The (width, height) that resize increments are counted from.
+    pub base_size: (u32, u32),
+    /// The window should be its base size plus a whole number of these (width, height) steps,
+    /// eg the size of a character in a terminal.
+    pub resize_increments: (u32, u32),
+    /// The (min, max) aspect ratios the window can be, if it is fussy.
+    pub aspect_range: Option<(f32, f32)>,
+}
+
+impl SizeHints {
+    /// No constraints at all, handy as a base for struct update syntax.
+    pub const NONE: SizeHints = SizeHints {
+        min_size: (0, 0),
+        max_size: None,
+        base_size: (0, 0),
+        resize_increments: (1, 1),
+        aspect_range: None,
+    };
+
+    /// Brings the given aspect ratio within the range these hints allow, if they have one.
+    pub fn clamp_aspect_ratio(&self, ratio: f32) -> f32 {
+        match self.aspect_range {
+            Some((min_aspect, max_aspect)) => ratio.max(min_aspect).min(max_aspect),
+            None => ratio,
+        }
+    }
+
+    /// Shrinks the region to the largest size these hints allow, keeping it centered.  Minimum
+    /// sizes can't be honored here (growing would overlap the neighbors), that is up to the
+    /// layout.
+    pub fn apply(&self, r: &Region) -> Region {
+        let (x, y, w, h) = r.values();
+        let (mut new_w, mut new_h) = (w, h);
+
+        if let Some((max_w, max_h)) = self.max_size {
+            new_w = new_w.min(max_w);
+            new_h = new_h.min(max_h);
+        }
+        if let Some((min_aspect, max_aspect)) = self.aspect_range {
+            let aspect = new_w as f32 / new_h as f32;
+            if aspect > max_aspect {
+                new_w = (new_h as f32 * max_aspect) as u32;
+            } else if aspect < min_aspect {
+                new_h = (new_w as f32 / min_aspect) as u32;
+            }
+        }
+        new_w = snap_to_increment(new_w, self.base_size.0, self.resize_increments.0);
+        new_h = snap_to_increment(new_h, self.base_size.1, self.resize_increments.1);
+
+        Region::new(x + (w - new_w) / 2, y + (h - new_h) / 2, new_w, new_h)
+    }
+}
+
+fn snap_to_increment(length: u32, base: u32, increment: u32) -> u32 {
+    if increment <= 1 || length < base {
+        length
+    } else {
+        base + (length - base) / increment * increment
+    }
+}
+
+/// The order in which the central main layouts hand out windows to the areas either side of the
+/// main region.  In the vertical layouts left means the top and right means the bottom.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillOrder {
+    /// Windows alternate between the right and left areas, starting with the right.
+    Alternating,
+    /// The left area gets the first half of the windows, and the right area the rest.
+    LeftFirst,
+    /// The right area gets the first half of the windows, and the left area the rest.
+    RightFirst,
+}
+
+/// The default settings, which aim for 16:9 windows (great for video).
+pub struct Widescreen;
+
+impl LayoutSettings for Widescreen {}
+
+/// Settings that aim for 4:3 windows (great for terminals), stacking any too small to work in.
+pub struct Standard;
+
+impl LayoutSettings for Standard {
+    const TARGET_ASPECT_RATIO: f32 = 4.0 / 3.0;
+    const MIN_TILE_SIZE: (u32, u32) = (640, 360);
+}
+
+/// Settings that letterbox windows to exactly 16:9 (great for screen recording).
+pub struct Letterboxed;
+
+impl LayoutSettings for Letterboxed {
+    const LETTERBOX: bool = true;
+}
+
 /// This layout has one large central main area, with peripheral windows tiled in an aspect ratio
 /// aware way.
-pub fn make_horizontal_central_main_layout() -> Layout {
+pub fn make_horizontal_central_main_layout<S: LayoutSettings>(symbol: &'static str) -> Layout {
     // TODO: should these constants be parameters?
-    Layout::new("[focus]", LayoutConf::default(), horizontal_central_main::new(), 2, 0.5)
+    Layout::new(symbol, LayoutConf::default(), horizontal_central_main_func::<S>(), 2, 0.5)
+}
+
+/// This layout is the horizontal central main layout rotated for portrait monitors; it has one
+/// large central band, with peripheral windows tiled above and below it.
+pub fn make_vertical_central_main_layout<S: LayoutSettings>(symbol: &'static str) -> Layout {
+    Layout::new(symbol, LayoutConf::default(), vertical_central_main_func::<S>(), 2, 0.5)
 }
 
 /// This layout tries to give all windows equal real estate (in an aspect ratio aware way).
-pub fn make_fair_layout() -> Layout {
-    Layout::new("[fair]", LayoutConf::default(), fair::new(), 1, 0.5)
+pub fn make_fair_layout<S: LayoutSettings>(symbol: &'static str) -> Layout {
+    Layout::new(symbol, fair_layout_conf::<S>(), fair_func::<S>(), 1, 0.5)
+}
+
+/// Like the fair layout, but tiles in columns rather than rows, so the lucky windows that get
+/// extra space are stacked above one another.  Well suited to tall (eg rotated) monitors.
+pub fn make_fair_vertical_layout<S: LayoutSettings>(symbol: &'static str) -> Layout {
+    Layout::new(symbol, fair_layout_conf::<S>(), fair_vertical_func::<S>(), 1, 0.5)
+}
+
+fn fair_layout_conf<S: LayoutSettings>() -> LayoutConf {
+    // Focusing a window in the overflow stack brings it to the top, which takes a fresh layout.
+    let follow_focus = S::MIN_TILE_SIZE != (0, 0);
+    LayoutConf { follow_focus, ..LayoutConf::default() }
+}
+
+/// This layout gives each window a share (the main region ratio) of the space left over by the
+/// windows before it, spiraling inwards, with each cut going whichever way best suits the windows'
+/// aspect ratios.
+pub fn make_spiral_layout<S: LayoutSettings>(symbol: &'static str) -> Layout {
+    // Focusing a window in the overflow stack brings it to the top, which takes a fresh layout.
+    Layout::new(symbol, follow_focus_conf(), spiral_func::<S>(), 1, 0.5)
+}
+
+/// This layout gives every window a full height column of (as near as possible) its target aspect
+/// ratio, in a strip that scrolls to keep the focused window on screen.  Good for workspaces with
+/// far too many windows to share the screen fairly.
+pub fn make_scrolling_layout<S: LayoutSettings>(symbol: &'static str) -> Layout {
+    // Following focus makes penrose lay the windows out again whenever the focus moves, which is
+    // what scrolls the strip.
+    Layout::new(symbol, follow_focus_conf(), scrolling_func::<S>(), 1, 0.5)
+}
+
+/// This layout leaves the tiling up to you: each new window splits the focused one in two, and
+/// the splits can then be rearranged (see the bsp module for the commands).
+pub fn make_bsp_layout<S: LayoutSettings>(symbol: &'static str) -> Layout {
+    Layout::new(symbol, LayoutConf::default(), bsp_func::<S>(), 1, 0.5)
+}
+
+/// This layout shows only the focused window, below a strip for the tab strip widget (see
+/// widgets::tab_strip) to list the other windows in.  Good for small screens.
+pub fn make_tabbed_layout<S: LayoutSettings>(symbol: &'static str) -> Layout {
+    // The focused window is the only one shown, so moving the focus has to lay out the windows
+    // again.
+    Layout::new(symbol, follow_focus_conf(), tabbed_func::<S>(), 1, 0.5)
+}
+
+/// This layout puts the windows side by side, giving the focused one a large share (the main
+/// region ratio) of the width and squeezing the others into slivers.
+pub fn make_accordion_layout<S: LayoutSettings>(symbol: &'static str) -> Layout {
+    // Moving the focus changes which window is expanded, so it has to lay the windows out again.
+    Layout::new(symbol, follow_focus_conf(), accordion_func::<S>(), 1, 0.75)
+}
+
+/// Like the accordion layout, but with the windows stacked one above another.
+pub fn make_accordion_vertical_layout<S: LayoutSettings>(symbol: &'static str) -> Layout {
+    Layout::new(symbol, follow_focus_conf(), accordion_vertical_func::<S>(), 1, 0.75)
+}
+
+/// The config for layouts that lay the windows out again whenever the focus moves.
+fn follow_focus_conf() -> LayoutConf {
+    LayoutConf { follow_focus: true, ..LayoutConf::default() }
+}
+
+/// This layout is whatever the given description says it is (see the description module), eg
+/// `columns(0.25: fair, ratio: main, 0.25: fair)`.
+pub fn make_described_layout<S: LayoutSettings>(
+    symbol: &'static str,
+    description: description::Description,
+) -> Result<Layout, OutOfSlots> {
+    Ok(Layout::new(symbol, LayoutConf::default(), described_func::<S>(description)?, 1, 0.5))
+}
+
+/// One of the layouts the cat ships with, along with what it takes to run the layout by hand (eg
+/// to preview it).
+pub struct DefaultLayout {
+    /// The layout, as the config sets it up.
+    pub layout: Layout,
+    /// The function behind the layout.
+    pub func: LayoutFunc,
+    /// How many windows the layout starts out with in its main region.
+    pub max_main: u32,
+    /// How much of the screen the layout starts out giving its main region.
+    pub ratio: f32,
+    /// Works out what the layout aims for each window to be.
+    pub targets: fn(&[&Client]) -> Vec<Target>,
+    /// How the layout scores its tilings.
+    pub cost: &'static dyn Cost,
+}
+
+impl DefaultLayout {
+    fn new<S: LayoutSettings>(
+        symbol: &'static str,
+        conf: LayoutConf,
+        func: LayoutFunc,
+        max_main: u32,
+        ratio: f32,
+    ) -> Self {
+        let layout = Layout::new(symbol, conf, func, max_main, ratio);
+        DefaultLayout { layout, func, max_main, ratio, targets: S::targets_for, cost: S::COST }
+    }
+}
+
+/// The layouts the cat ships with, in the order it cycles through them.
+pub fn default_layouts() -> Vec<DefaultLayout> {
+    let plain = LayoutConf::default();
+    vec![
+        DefaultLayout::new::<Widescreen>(
+            "[focus]",
+            plain,
+            horizontal_central_main_func::<Widescreen>(),
+            2,
+            0.5,
+        ),
+        DefaultLayout::new::<Widescreen>(
+            "[vfocus]",
+            plain,
+            vertical_central_main_func::<Widescreen>(),
+            2,
+            0.5,
+        ),
+        DefaultLayout::new::<Widescreen>(
+            "[spiral]",
+            follow_focus_conf(),
+            spiral_func::<Widescreen>(),
+            1,
+            0.5,
+        ),
+        DefaultLayout::new::<Widescreen>(
+            "[fair]",
+            fair_layout_conf::<Widescreen>(),
+            fair_func::<Widescreen>(),
+            1,
+            0.5,
+        ),
+        DefaultLayout::new::<Standard>(
+            "[term]",
+            fair_layout_conf::<Standard>(),
+            fair_func::<Standard>(),
+            1,
+            0.5,
+        ),
+        DefaultLayout::new::<Widescreen>(
+            "[vfair]",
+            fair_layout_conf::<Widescreen>(),
+            fair_vertical_func::<Widescreen>(),
+            1,
+            0.5,
+        ),
+        DefaultLayout::new::<Standard>(
+            "[scroll]",
+            follow_focus_conf(),
+            scrolling_func::<Standard>(),
+            1,
+            0.5,
+        ),
+        DefaultLayout::new::<Widescreen>("[bsp]", plain, bsp_func::<Widescreen>(), 1, 0.5),
+        DefaultLayout::new::<Widescreen>(
+            "[tabs]",
+            follow_focus_conf(),
+            tabbed_func::<Widescreen>(),
+            1,
+            0.5,
+        ),
+        DefaultLayout::new::<Widescreen>(
+            "[accordion]",
+            follow_focus_conf(),
+            accordion_func::<Widescreen>(),
+            1,
+            0.75,
+        ),
+        DefaultLayout::new::<Letterboxed>(
+            "[box]",
+            plain,
+            horizontal_central_main_func::<Letterboxed>(),
+            2,
+            0.5,
+        ),
+    ]
+}
+
+/// The layout function behind `make_horizontal_central_main_layout`.
+pub fn horizontal_central_main_func<S: LayoutSettings>() -> LayoutFunc {
+    horizontal_central_main::new::<S>()
+}
+
+/// The layout function behind `make_vertical_central_main_layout`.
+pub fn vertical_central_main_func<S: LayoutSettings>() -> LayoutFunc {
+    vertical_central_main::new::<S>()
+}
+
+/// The layout function behind `make_fair_layout`.
+pub fn fair_func<S: LayoutSettings>() -> LayoutFunc {
+    fair::new::<S>()
+}
+
+/// The layout function behind `make_fair_vertical_layout`.
+pub fn fair_vertical_func<S: LayoutSettings>() -> LayoutFunc {
+    fair::new_vertical::<S>()
+}
+
+/// The layout function behind `make_bsp_layout`.
+pub fn bsp_func<S: LayoutSettings>() -> LayoutFunc {
+    bsp::new::<S>()
+}
+
+/// The layout function behind `make_tabbed_layout`.
+pub fn tabbed_func<S: LayoutSettings>() -> LayoutFunc {
+    tabbed::new::<S>()
+}
+
+/// The layout function behind `make_accordion_layout`.
+pub fn accordion_func<S: LayoutSettings>() -> LayoutFunc {
+    accordion::new::<S>()
+}
+
+/// The layout function behind `make_accordion_vertical_layout`.
+pub fn accordion_vertical_func<S: LayoutSettings>() -> LayoutFunc {
+    accordion::new_vertical::<S>()
+}
+
+/// The layout function behind `make_spiral_layout`.
+pub fn spiral_func<S: LayoutSettings>() -> LayoutFunc {
+    spiral::new::<S>()
+}
+
+/// The layout function behind `make_scrolling_layout`.
+pub fn scrolling_func<S: LayoutSettings>() -> LayoutFunc {
+    scrolling::new::<S>()
+}
+
+/// The layout function behind `make_described_layout`.  Like the combinators below, each call
+/// builds a new layout function, and only so many can be built.
+pub fn described_func<S: LayoutSettings>(
+    description: description::Description,
+) -> Result<LayoutFunc, OutOfSlots> {
+    dynamic::register(move |clients, _, to_fill, max_main, ratio| {
+        S::fit_windows(
+            clients,
+            description::layout::<S>(&description, clients, to_fill, max_main, ratio),
+        )
+    })
+}
+
+/// Wraps a layout function so that its layouts come out mirrored, left to right.
+pub fn mirrored(f: LayoutFunc) -> Result<LayoutFunc, OutOfSlots> {
+    transformed(f, |r| *r, utils::mirror)
+}
+
+/// Wraps a layout function so that its layouts come out flipped, top to bottom.
+pub fn flipped(f: LayoutFunc) -> Result<LayoutFunc, OutOfSlots> {
+    transformed(f, |r| *r, utils::flip)
+}
+
+/// Wraps a layout function so that its layouts come out transposed (reflected across the
+/// diagonal), so rows become columns and vice versa.
+pub fn transposed(f: LayoutFunc) -> Result<LayoutFunc, OutOfSlots> {
+    transformed(f, |r| utils::transpose(r, r), utils::transpose)
+}
+
+/// Wraps a layout function so that its layouts come out rotated a quarter turn clockwise.
+pub fn rotated_clockwise(f: LayoutFunc) -> Result<LayoutFunc, OutOfSlots> {
+    mirrored(transposed(f)?)
+}
+
+/// Wraps a layout function so that its layouts come out rotated a quarter turn anticlockwise.
+pub fn rotated_anticlockwise(f: LayoutFunc) -> Result<LayoutFunc, OutOfSlots> {
+    flipped(transposed(f)?)
+}
+
+/// Lays out the windows in `to_inner(screen)` and then maps each of the resulting regions back
+/// onto the screen with `from_inner`.
+fn transformed(
+    f: LayoutFunc,
+    to_inner: fn(&Region) -> Region,
+    from_inner: fn(&Region, &Region) -> Region,
+) -> Result<LayoutFunc, OutOfSlots> {
+    dynamic::register(move |clients, focused, to_fill, max_main, ratio| {
+        f(clients, focused, &to_inner(to_fill), max_main, ratio)
+            .into_iter()
+            .map(|(id, r)| (id, r.map(|r| from_inner(&r, to_fill))))
+            .collect()
+    })
+}
+
+/// Which way a split layout divides the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitDirection {
+    /// The first part is on the left, and the second part on the right.
+    LeftRight,
+    /// The first part is on top, and the second part below it.
+    TopBottom,
+}
+
+/// Which windows a split layout hands to its first part; the rest go to the second part.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitClients {
+    /// The first n windows.
+    First(usize),
+    /// The focused window, if there is one.
+    Focused,
+}
+
+/// Builds a layout function out of two others, giving the first `ratio` of the screen to `first`
+/// (which lays out the windows picked by `clients`) and the rest to `second` (which lays out the
+/// remaining windows).  If either part has no windows the other gets the whole screen.
+///
+/// There is no gap between the parts, each part's outer gap separates them.
+pub fn split(
+    direction: SplitDirection,
+    ratio: f32,
+    clients: SplitClients,
+    first: LayoutFunc,
+    second: LayoutFunc,
+) -> Result<LayoutFunc, OutOfSlots> {
+    dynamic::register(move |all, focused, to_fill, max_main, main_ratio| {
+        let (first_clients, second_clients): (Vec<&Client>, Vec<&Client>) = match clients {
+            SplitClients::First(n) => {
+                (all.iter().take(n).copied().collect(), all[n.min(all.len())..].to_vec())
+            }
+            SplitClients::Focused => all.iter().copied().partition(|c| Some(c.id()) == focused),
+        };
+        if first_clients.is_empty() {
+            return second(&second_clients, focused, to_fill, max_main, main_ratio);
+        } else if second_clients.is_empty() {
+            return first(&first_clients, focused, to_fill, max_main, main_ratio);
+        }
+
+        let (first_region, second_region) = utils::split(to_fill, direction, ratio, 0);
+
+        main_region::laying_out_parts(all, || {
+            let mut actions = first(&first_clients, focused, &first_region, max_main, main_ratio);
+            actions.extend(second(&second_clients, focused, &second_region, max_main, main_ratio));
+            actions
+        })
+    })
 }
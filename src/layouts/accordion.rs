@@ -0,0 +1,58 @@
+//! The accordion layout gives the focused window a large share of the screen and squeezes the
+//! rest into slivers either side of it, so moving the focus along opens up the next window like
+//! the bellows of an accordion.
+//!
+//! There are two variants: the (horizontal) accordion puts the windows side by side, and the
+//! vertical accordion stacks them one above another.  The share the focused window gets is the
+//! layout's main region ratio, so it can be adjusted on the fly.
+//!
+
+use penrose::{
+    client::Client,
+    data_types::{Region, ResizeAction, WinId},
+    layout::LayoutFunc,
+};
+
+use super::{utils, validation, LayoutSettings};
+
+/// Creates and returns a closure that performs horizontal accordion layouts.
+pub fn new<S: LayoutSettings>() -> LayoutFunc {
+    |a, b, c, _, e| {
+        let layout =
+            do_accordion_layout(a, b, c, e, S::gaps(a.len()), utils::split_into_weighted_columns);
+        S::fit_windows(a, layout)
+    }
+}
+
+/// Creates and returns a closure that performs vertical accordion layouts.
+pub fn new_vertical<S: LayoutSettings>() -> LayoutFunc {
+    |a, b, c, _, e| {
+        let layout =
+            do_accordion_layout(a, b, c, e, S::gaps(a.len()), utils::split_into_weighted_rows);
+        S::fit_windows(a, layout)
+    }
+}
+
+fn do_accordion_layout(
+    clients: &[&Client],
+    focused: Option<WinId>,
+    to_fill: &Region,
+    focused_share: f32,
+    (gap, outer_gap): (u32, u32),
+    split: fn(&Region, &[f32], u32) -> Vec<Region>,
+) -> Vec<ResizeAction> {
+    if clients.is_empty() {
+        return Vec::new();
+    }
+
+    let focused = focused.and_then(|id| clients.iter().position(|c| c.id() == id)).unwrap_or(0);
+    let sliver_share = (1.0 - focused_share) / (clients.len() - 1).max(1) as f32;
+    let weights: Vec<f32> = (0..clients.len())
+        .map(|i| if i == focused { focused_share } else { sliver_share })
+        .collect();
+
+    let to_fill = &utils::shrink(to_fill, outer_gap);
+    let layout = split(to_fill, &weights, gap);
+    let actions = clients.iter().zip(layout).map(|(c, r)| (c.id(), Some(r))).collect();
+    validation::debug_checked(clients, to_fill, gap, actions)
+}
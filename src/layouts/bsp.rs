@@ -0,0 +1,348 @@
+//! The binary space partition layout leaves the tiling up to you (a la i3 and bspwm).
+//!
+//! Each workspace keeps a tree of splits, and a new window splits the region of the window that
+//! was focused, either in the direction preselected with `preselect_left_right` or
+//! `preselect_top_bottom`, or (by default) whichever way leaves both windows closest to their
+//! target aspect ratios.  A split that would leave a window smaller than `utils::MIN_SPLIT_PX`
+//! goes the other way, or splits the largest window with room instead, and when no window has room
+//! the new window stays unmapped until one does.  Closing a window gives its space back to its
+//! sibling.  The splits can be rearranged with `rotate`, `flip`, `grow` and `shrink`, which act on
+//! the split the focused window was made by.
+//!
+
+use std::{cell::RefCell, collections::HashMap};
+
+use penrose::{
+    client::Client,
+    core::ring::Selector,
+    data_types::{Region, ResizeAction, WinId},
+    layout::LayoutFunc,
+    WindowManager,
+};
+
+use super::{utils, validation, LayoutSettings, SplitDirection, Target};
+
+/// How much `grow` and `shrink` move a split by.
+const RESIZE_STEP: f32 = 0.05;
+
+#[derive(Clone, Debug)]
+enum Node {
+    Leaf(WinId),
+    Split { direction: SplitDirection, ratio: f32, first: Box<Node>, second: Box<Node> },
+}
+
+/// Everything remembered about a workspace between layouts.
+#[derive(Debug, Default)]
+struct Tree {
+    root: Option<Node>,
+    /// The window new windows split, since penrose focuses a new window before laying it out.
+    last_focused: Option<WinId>,
+    /// The direction to split in next, if one has been picked.
+    preselected: Option<SplitDirection>,
+}
+
+thread_local! {
+    // Like the main region, the trees have to outlive the (plain function) layouts.
+    static TREES: RefCell<HashMap<usize, Tree>> = RefCell::new(HashMap::new());
+}
+
+/// Creates and returns a closure that performs binary space partition layouts.
+pub fn new<S: LayoutSettings>() -> LayoutFunc {
+    |a, b, c, _, _| S::fit_windows(a, do_bsp_layout(a, b, c, &S::targets_for(a), S::gaps(a.len())))
+}
+
+fn do_bsp_layout(
+    clients: &[&Client],
+    focused: Option<WinId>,
+    to_fill: &Region,
+    targets: &[Target],
+    (gap, outer_gap): (u32, u32),
+) -> Vec<ResizeAction> {
+    let workspace = match clients.first() {
+        Some(c) => c.workspace(),
+        None => return Vec::new(),
+    };
+    let to_fill = &utils::shrink(to_fill, outer_gap);
+    let ids: Vec<WinId> = clients.iter().map(|c| c.id()).collect();
+
+    let regions = TREES.with(|t| {
+        let mut t = t.borrow_mut();
+        let tree = t.entry(workspace).or_default();
+        tree.root = tree.root.take().and_then(|root| prune(root, &ids));
+
+        for (i, id) in ids.iter().enumerate() {
+            if !matches!(&tree.root, Some(root) if contains(root, *id)) {
+                insert(tree, to_fill, gap, (*id, targets[i].aspect_ratio), &ids, targets);
+            }
+        }
+        if let Some(id) = focused.filter(|id| ids.contains(id)) {
+            tree.last_focused = Some(id);
+        }
+
+        let mut regions = Vec::with_capacity(ids.len());
+        if let Some(root) = &tree.root {
+            arrange(root, to_fill, gap, &mut regions);
+        }
+        regions
+    });
+
+    let actions = ids
+        .iter()
+        .map(|id| (*id, regions.iter().find(|(i, _)| i == id).map(|(_, r)| *r)))
+        .collect();
+    validation::debug_checked(clients, to_fill, gap, actions)
+}
+
+/// Adds a window to the tree by splitting the last focused window (or the last window, if that's
+/// gone) in two, or if that's too small, the largest window that isn't.  The window is left out if
+/// none of them are big enough to split.
+fn insert(
+    tree: &mut Tree,
+    to_fill: &Region,
+    gap: u32,
+    (id, target): (WinId, f32),
+    ids: &[WinId],
+    targets: &[Target],
+) {
+    let root = match tree.root.as_mut() {
+        Some(root) => root,
+        None => {
+            tree.root = Some(Node::Leaf(id));
+            return;
+        }
+    };
+
+    let last_focused = tree.last_focused;
+    let mut regions = Vec::new();
+    arrange(root, to_fill, gap, &mut regions);
+    let first_choice =
+        regions.iter().position(|(i, _)| Some(*i) == last_focused).unwrap_or(regions.len() - 1);
+    let first_choice = regions.remove(first_choice);
+    regions.sort_by_key(|(_, r)| {
+        let (_, _, w, h) = r.values();
+        std::cmp::Reverse(w * h)
+    });
+    regions.insert(0, first_choice);
+
+    let preselected = tree.preselected;
+    let split_direction = |(split_id, split_region): &(WinId, Region)| {
+        let preferred = preselected.unwrap_or_else(|| {
+            let split_target =
+                ids.iter().position(|i| i == split_id).map_or(target, |i| targets[i].aspect_ratio);
+            utils::best_split_direction(split_region, gap, (split_target, target))
+        });
+        let other = match preferred {
+            SplitDirection::LeftRight => SplitDirection::TopBottom,
+            SplitDirection::TopBottom => SplitDirection::LeftRight,
+        };
+        let fits = |direction: &SplitDirection| {
+            let (first, second) = utils::split(split_region, *direction, 0.5, gap);
+            utils::big_enough_to_split(&first) && utils::big_enough_to_split(&second)
+        };
+        [preferred, other].iter().copied().find(fits).map(|direction| (*split_id, direction))
+    };
+    let (split_id, direction) = match regions.iter().find_map(split_direction) {
+        Some(split) => split,
+        None => return,
+    };
+
+    if let Some(leaf) = find_leaf(root, split_id) {
+        *leaf = Node::Split {
+            direction,
+            ratio: 0.5,
+            first: Box::new(Node::Leaf(split_id)),
+            second: Box::new(Node::Leaf(id)),
+        };
+    }
+    tree.preselected = None;
+    // New windows split the window before them, so keep going from this one.
+    tree.last_focused = Some(id);
+}
+
+/// Drops the windows that have gone, handing their space to their siblings.
+fn prune(node: Node, ids: &[WinId]) -> Option<Node> {
+    match node {
+        Node::Leaf(id) => Some(node).filter(|_| ids.contains(&id)),
+        Node::Split { direction, ratio, first, second } => {
+            match (prune(*first, ids), prune(*second, ids)) {
+                (Some(first), Some(second)) => Some(Node::Split {
+                    direction,
+                    ratio,
+                    first: Box::new(first),
+                    second: Box::new(second),
+                }),
+                (Some(only), None) | (None, Some(only)) => Some(only),
+                (None, None) => None,
+            }
+        }
+    }
+}
+
+fn contains(node: &Node, id: WinId) -> bool {
+    match node {
+        Node::Leaf(i) => *i == id,
+        Node::Split { first, second, .. } => contains(first, id) || contains(second, id),
+    }
+}
+
+fn find_leaf(node: &mut Node, id: WinId) -> Option<&mut Node> {
+    match node {
+        Node::Leaf(i) if *i == id => Some(node),
+        Node::Leaf(_) => None,
+        Node::Split { first, second, .. } => {
+            if contains(first, id) {
+                find_leaf(first, id)
+            } else {
+                find_leaf(second, id)
+            }
+        }
+    }
+}
+
+/// Finds the split that made the window's region.
+fn find_parent(node: &mut Node, id: WinId) -> Option<&mut Node> {
+    let (in_first, is_child) = match node {
+        Node::Leaf(_) => return None,
+        Node::Split { first, second, .. } => {
+            if contains(first, id) {
+                (true, matches!(**first, Node::Leaf(_)))
+            } else if contains(second, id) {
+                (false, matches!(**second, Node::Leaf(_)))
+            } else {
+                return None;
+            }
+        }
+    };
+
+    if is_child {
+        return Some(node);
+    }
+    match node {
+        Node::Split { first, second, .. } => find_parent(if in_first { first } else { second }, id),
+        Node::Leaf(_) => None,
+    }
+}
+
+fn arrange(node: &Node, r: &Region, gap: u32, results: &mut Vec<(WinId, Region)>) {
+    match node {
+        Node::Leaf(id) => results.push((*id, *r)),
+        Node::Split { direction, ratio, first, second } => {
+            let (first_region, second_region) = utils::split(r, *direction, *ratio, gap);
+            arrange(first, &first_region, gap, results);
+            arrange(second, &second_region, gap, results);
+        }
+    }
+}
+
+/// Makes the next window opened on the focused workspace split the focused window side by side.
+pub fn preselect_left_right(wm: &mut WindowManager) {
+    preselect(wm, SplitDirection::LeftRight);
+}
+
+/// Makes the next window opened on the focused workspace split the focused window one above the
+/// other.
+pub fn preselect_top_bottom(wm: &mut WindowManager) {
+    preselect(wm, SplitDirection::TopBottom);
+}
+
+fn preselect(wm: &mut WindowManager, direction: SplitDirection) {
+    let workspace = wm.active_workspace();
+    TREES.with(|t| t.borrow_mut().entry(workspace).or_default().preselected = Some(direction));
+}
+
+/// Turns the split the focused window was made by a quarter turn, so side by side windows end up
+/// one above the other and vice versa.
+pub fn rotate(wm: &mut WindowManager) {
+    with_parent_split(wm, |direction, _, _, _| {
+        *direction = match direction {
+            SplitDirection::LeftRight => SplitDirection::TopBottom,
+            SplitDirection::TopBottom => SplitDirection::LeftRight,
+        }
+    });
+}
+
+/// Swaps the two sides of the split the focused window was made by.
+pub fn flip(wm: &mut WindowManager) {
+    with_parent_split(wm, |_, ratio, first, second| {
+        std::mem::swap(first, second);
+        *ratio = 1.0 - *ratio;
+    });
+}
+
+/// Gives the focused window's side of its split more of the space.
+pub fn grow(wm: &mut WindowManager) {
+    resize(wm, RESIZE_STEP);
+}
+
+/// Gives the focused window's side of its split less of the space.
+pub fn shrink(wm: &mut WindowManager) {
+    resize(wm, -RESIZE_STEP);
+}
+
+fn resize(wm: &mut WindowManager, step: f32) {
+    let id = match wm.client(&Selector::Focused) {
+        Some(c) => c.id(),
+        None => return,
+    };
+    with_parent_split(wm, |_, ratio, first, _| {
+        let step = if contains(first, id) { step } else { -step };
+        *ratio = (*ratio + step).clamp(RESIZE_STEP, 1.0 - RESIZE_STEP);
+    });
+}
+
+/// Applies the change to the split the focused window was made by, then lays the screen out again.
+fn with_parent_split<F>(wm: &mut WindowManager, change: F)
+where
+    F: FnOnce(&mut SplitDirection, &mut f32, &mut Box<Node>, &mut Box<Node>),
+{
+    let (id, workspace) = match wm.client(&Selector::Focused) {
+        Some(c) => (c.id(), c.workspace()),
+        None => return,
+    };
+    TREES.with(|t| {
+        let mut t = t.borrow_mut();
+        let parent = t
+            .get_mut(&workspace)
+            .and_then(|tree| tree.root.as_mut())
+            .and_then(|root| find_parent(root, id));
+        if let Some(Node::Split { direction, ratio, first, second }) = parent {
+            change(direction, ratio, first, second);
+        }
+    });
+    wm.layout_screen(wm.active_screen_index());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::validation;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn tiles_the_screen(
+            w in 640u32..7680,
+            h in 480u32..2160,
+            aspect_ratios in prop::collection::vec(0.5f32..3.0, 1..60),
+            gap in 0u32..12,
+        ) {
+            TREES.with(|t| t.borrow_mut().clear());
+            let clients: Vec<Client> = (0..aspect_ratios.len())
+                .map(|i| Client::new(i as u32, "hi".to_string(), "there".to_string(), 1, false))
+                .collect();
+            let refs: Vec<&Client> = clients.iter().collect();
+            let targets: Vec<Target> = aspect_ratios
+                .iter()
+                .map(|&aspect_ratio| Target { aspect_ratio, min_size: (0, 0) })
+                .collect();
+            let screen = Region::new(0, 0, w, h);
+
+            // The windows there is no room for are left unmapped.
+            let layout = do_bsp_layout(&refs, None, &screen, &targets, (gap, 0));
+            prop_assert_eq!(layout.len(), refs.len());
+            let shown: Vec<Region> = layout.iter().filter_map(|(_, r)| *r).collect();
+            let n = shown.len();
+            prop_assert_eq!(validation::validate(&screen, &shown, n, gap), Ok(()));
+        }
+    }
+}
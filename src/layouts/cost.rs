@@ -0,0 +1,131 @@
+//! Costs are how layouts choose between the tilings they could use: the tiling with the lowest
+//! cost wins.  Which cost a layout uses is part of its settings (see `LayoutSettings::COST`), so
+//! different layouts can aim for different things.
+//!
+
+use penrose::data_types::Region;
+
+use super::{utils, Target};
+
+/// Scores how well a tiling suits its windows, lower is better.
+pub trait Cost {
+    /// Computes the cost of giving each window (by its target) the region beside it in `layout`.
+    fn cost(&self, layout: &[Region], targets: &[Target]) -> f32;
+
+    /// Puts together the costs of two parts of a tiling, for searches that score the parts
+    /// separately (eg the guillotine one).  By default the costs add up.
+    fn combine(&self, a: f32, b: f32) -> f32 {
+        a + b
+    }
+}
+
+/// The sum of the square errors of each window's aspect ratio versus its target ratio.  This is
+/// the default, it gets most windows close to their target at the expense of a few outliers.
+#[derive(Clone, Copy, Debug)]
+pub struct AspectRatioSse;
+
+impl Cost for AspectRatioSse {
+    fn cost(&self, layout: &[Region], targets: &[Target]) -> f32 {
+        layout
+            .iter()
+            .zip(targets)
+            .map(|(r, t)| (utils::aspect_ratio(r) - t.aspect_ratio).powi(2))
+            // Summing floats starts from -0.0, which would make an empty layout cost -0.0.
+            .fold(0.0, |sum, error| sum + error)
+    }
+}
+
+/// The error of the window whose aspect ratio is furthest from its target, for when one badly
+/// squashed window is worse than several slightly squashed ones.
+#[derive(Clone, Copy, Debug)]
+pub struct MaxDeviation;
+
+impl Cost for MaxDeviation {
+    fn cost(&self, layout: &[Region], targets: &[Target]) -> f32 {
+        layout
+            .iter()
+            .zip(targets)
+            .map(|(r, t)| (utils::aspect_ratio(r) - t.aspect_ratio).abs())
+            .fold(0.0, f32::max)
+    }
+
+    fn combine(&self, a: f32, b: f32) -> f32 {
+        a.max(b)
+    }
+}
+
+/// The variance of the windows' shares of the total area, for when every window getting the same
+/// amount of space matters more than their shapes.
+#[derive(Clone, Copy, Debug)]
+pub struct AreaVariance;
+
+impl Cost for AreaVariance {
+    fn cost(&self, layout: &[Region], _: &[Target]) -> f32 {
+        let areas: Vec<f32> =
+            layout.iter().map(|r| r.values().2 as f32 * r.values().3 as f32).collect();
+        let total: f32 = areas.iter().sum();
+        if total == 0.0 {
+            return 0.0;
+        }
+
+        let mean = 1.0 / areas.len() as f32;
+        areas.iter().map(|a| (a / total - mean).powi(2)).sum::<f32>() / areas.len() as f32
+    }
+}
+
+/// Adds a penalty to another cost for every row beyond `max_rows`, for when lots of short rows
+/// are hard to read (eg terminals).
+#[derive(Clone, Copy)]
+pub struct RowPenalty {
+    /// The cost being penalized.
+    pub base: &'static dyn Cost,
+    /// How many rows are allowed before the penalty kicks in.
+    pub max_rows: usize,
+    /// The penalty for each extra row.
+    pub penalty: f32,
+}
+
+impl Cost for RowPenalty {
+    fn cost(&self, layout: &[Region], targets: &[Target]) -> f32 {
+        // Every row starts a new top edge.
+        let mut tops: Vec<u32> = layout.iter().map(|r| r.values().1).collect();
+        tops.sort_unstable();
+        tops.dedup();
+
+        let extra_rows = tops.len().saturating_sub(self.max_rows);
+        self.base.cost(layout, targets) + extra_rows as f32 * self.penalty
+    }
+
+    fn combine(&self, a: f32, b: f32) -> f32 {
+        self.base.combine(a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET: Target = Target { aspect_ratio: 1.0, min_size: (0, 0) };
+
+    #[test]
+    fn scores_shapes_and_areas() {
+        let squares = [Region::new(0, 0, 100, 100), Region::new(100, 0, 100, 100)];
+        let uneven = [Region::new(0, 0, 100, 100), Region::new(100, 0, 200, 100)];
+
+        assert_eq!(AspectRatioSse.cost(&squares, &[TARGET; 2]), 0.0);
+        assert_eq!(AspectRatioSse.cost(&uneven, &[TARGET; 2]), 1.0);
+        assert_eq!(MaxDeviation.cost(&uneven, &[TARGET; 2]), 1.0);
+        assert_eq!(AreaVariance.cost(&squares, &[TARGET; 2]), 0.0);
+        assert!(AreaVariance.cost(&uneven, &[TARGET; 2]) > 0.0);
+        // Not -0.0.
+        assert!(AspectRatioSse.cost(&[], &[]).is_sign_positive());
+    }
+
+    #[test]
+    fn penalizes_extra_rows() {
+        let rows =
+            [Region::new(0, 0, 100, 50), Region::new(0, 50, 100, 50), Region::new(0, 100, 100, 50)];
+        let cost = RowPenalty { base: &AspectRatioSse, max_rows: 2, penalty: 0.5 };
+        assert_eq!(cost.cost(&rows, &[TARGET; 3]), AspectRatioSse.cost(&rows, &[TARGET; 3]) + 0.5);
+    }
+}
@@ -0,0 +1,539 @@
+//! Layouts can be described rather than written in Rust.  A description is a tree of splits with
+//! windows at its leaves, for example a central main area between two fair areas:
+//!
+//! ```text
+//! columns(0.25: fair, ratio: main, 0.25: fair)
+//! ```
+//!
+//! - `rows(...)` stacks its parts one above another, and `columns(...)` puts them side by side.
+//!   Each part takes a share of the space in proportion to its weight (eg `0.25: fair`, the
+//!   default weight is 1), except that a part weighted `ratio` takes the layout's main region
+//!   ratio of the space, so it can be adjusted on the fly.
+//! - `main` holds the windows in the main region (see the main_region module), up to the
+//!   layout's max main unless it is given a capacity (eg `main(1)`).  Like the central main
+//!   layouts it gives the first of two windows the lion's share.
+//! - `fair` tiles its windows fairly (see the fair module).  It can be given a capacity too (eg
+//!   `fair(3)`); fair parts without one share out the windows left over, and if every part is
+//!   full the last one takes the rest.
+//!
+//! Parts without any windows are left out, and their space goes to the other parts.
+//!
+//! Descriptions can also be loaded from a config file (see `parse_config`), one per line:
+//!
+//! ```text
+//! # symbol = description
+//! [tall] = rows(ratio: main(1), fair)
+//! ```
+//!
+
+use std::fmt;
+
+use penrose::{
+    client::Client,
+    data_types::{Region, ResizeAction},
+};
+
+use super::{
+    fair::layout_region_fairly_with, horizontal_central_main::layout_main_with, main_region, utils,
+    validation, LayoutSettings, SplitDirection, Target,
+};
+
+/// A parsed layout description, ready to be made into a layout (see `make_described_layout`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Description {
+    root: Node,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Split(SplitDirection, Vec<(Weight, Node)>),
+    Leaf(Leaf, Option<usize>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Leaf {
+    Fair,
+    Main,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Weight {
+    Fixed(f32),
+    Ratio,
+}
+
+/// What went wrong parsing a description, and where.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    /// The line the problem is on, counting from 1.
+    pub line: usize,
+    /// The column the problem is in, counting from 1.
+    pub column: usize,
+    /// What the problem is.
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses a layout description.
+pub fn parse(text: &str) -> Result<Description, ParseError> {
+    let mut parser = Parser { chars: text.chars().collect(), pos: 0 };
+    let root = parser.node()?;
+    match parser.peek() {
+        None => Ok(Description { root }),
+        Some(c) => parser.error(format!("unexpected `{}` after the end of the layout", c)),
+    }
+}
+
+/// Parses a config file of `symbol = description` lines.  Blank lines and lines starting with
+/// `#` are skipped.
+pub fn parse_config(text: &str) -> Result<Vec<(String, Description)>, ParseError> {
+    let mut results = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let indent = line.chars().count() - trimmed.chars().count();
+        let (symbol, description) = match trimmed.find('=') {
+            Some(at) => (trimmed[..at].trim(), &trimmed[at + 1..]),
+            None => ("", ""),
+        };
+        if symbol.is_empty() {
+            let message = "expected `symbol = description`".to_string();
+            return Err(ParseError { line: i + 1, column: indent + 1, message });
+        }
+
+        // Errors in the description are relative to its start.
+        let offset = indent + trimmed.chars().count() - description.chars().count();
+        let parsed = parse(description).map_err(|e| ParseError {
+            line: i + 1,
+            column: e.column + offset,
+            message: e.message,
+        })?;
+        results.push((symbol.to_string(), parsed));
+    }
+    Ok(results)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error<T>(&self, message: String) -> Result<T, ParseError> {
+        let before = &self.chars[..self.pos];
+        let line = before.iter().filter(|c| **c == '\n').count() + 1;
+        let column = before.iter().rev().take_while(|c| **c != '\n').count() + 1;
+        Err(ParseError { line, column, message })
+    }
+
+    /// Skips any whitespace, then looks at the next character.
+    fn peek(&mut self) -> Option<char> {
+        while matches!(self.chars.get(self.pos), Some(c) if c.is_whitespace()) {
+            self.pos += 1;
+        }
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char, after: &str) -> Result<(), ParseError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            self.error(format!("expected `{}` after {}", c, after))
+        }
+    }
+
+    fn take_while(&mut self, f: fn(&char) -> bool) -> String {
+        self.peek();
+        let start = self.pos;
+        while matches!(self.chars.get(self.pos), Some(c) if f(c)) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn node(&mut self) -> Result<Node, ParseError> {
+        self.peek();
+        let start = self.pos;
+        let name = self.take_while(char::is_ascii_alphabetic);
+        self.named_node(&name, start)
+    }
+
+    fn named_node(&mut self, name: &str, start: usize) -> Result<Node, ParseError> {
+        match name {
+            "rows" => self.split(SplitDirection::TopBottom, name),
+            "columns" => self.split(SplitDirection::LeftRight, name),
+            "fair" => Ok(Node::Leaf(Leaf::Fair, self.capacity(name)?)),
+            "main" => Ok(Node::Leaf(Leaf::Main, self.capacity(name)?)),
+            "" => self.error("expected rows, columns, fair or main".to_string()),
+            _ => {
+                self.pos = start;
+                self.error(format!("unknown part `{}`, expected rows, columns, fair or main", name))
+            }
+        }
+    }
+
+    fn split(&mut self, direction: SplitDirection, name: &str) -> Result<Node, ParseError> {
+        self.expect('(', name)?;
+        let mut parts = Vec::new();
+        loop {
+            self.peek();
+            let start = self.pos;
+            let part = self.part()?;
+            if part.0 == Weight::Ratio && parts.iter().any(|(w, _)| *w == Weight::Ratio) {
+                self.pos = start;
+                return self.error(format!("only one part of {} can take the ratio", name));
+            }
+            parts.push(part);
+            if !self.eat(',') {
+                break;
+            }
+        }
+        self.expect(')', &format!("the parts of {}", name))?;
+        Ok(Node::Split(direction, parts))
+    }
+
+    fn part(&mut self) -> Result<(Weight, Node), ParseError> {
+        self.peek();
+        let start = self.pos;
+        let weight = self.take_while(|c| c.is_ascii_digit() || *c == '.');
+        if !weight.is_empty() {
+            let weight = match weight.parse::<f32>() {
+                Ok(w) if w > 0.0 && w.is_finite() => w,
+                _ => {
+                    self.pos = start;
+                    return self.error(format!("`{}` isn't a positive weight", weight));
+                }
+            };
+            self.expect(':', "a weight")?;
+            return Ok((Weight::Fixed(weight), self.node()?));
+        }
+
+        let name = self.take_while(char::is_ascii_alphabetic);
+        if name == "ratio" {
+            self.expect(':', "ratio")?;
+            Ok((Weight::Ratio, self.node()?))
+        } else {
+            Ok((Weight::Fixed(1.0), self.named_node(&name, start)?))
+        }
+    }
+
+    fn capacity(&mut self, name: &str) -> Result<Option<usize>, ParseError> {
+        if !self.eat('(') {
+            return Ok(None);
+        }
+        self.peek();
+        let start = self.pos;
+        let capacity = self.take_while(char::is_ascii_digit);
+        match capacity.parse::<usize>() {
+            Ok(n) if n > 0 => {
+                self.expect(')', &format!("the capacity of {}", name))?;
+                Ok(Some(n))
+            }
+            _ => {
+                self.pos = start;
+                self.error(format!("expected the number of windows {} can hold", name))
+            }
+        }
+    }
+}
+
+/// Lays out the clients as the description describes.
+pub fn layout<S: LayoutSettings>(
+    description: &Description,
+    clients: &[&Client],
+    to_fill: &Region,
+    max_main: u32,
+    ratio: f32,
+) -> Vec<ResizeAction> {
+    let mut leaves = Vec::new();
+    collect_leaves(&description.root, &mut leaves);
+    // The main region holds as many windows as the main leaves can.
+    let main_capacity: usize = leaves
+        .iter()
+        .filter(|(leaf, _)| *leaf == Leaf::Main)
+        .map(|(_, capacity)| capacity.unwrap_or(max_main as usize))
+        .sum();
+    let (clients, main_count) = if main_capacity > 0 {
+        main_region::main_first(clients, main_capacity as u32)
+    } else {
+        (clients.to_vec(), 0)
+    };
+    let clients = &clients;
+
+    let counts = share_out(&leaves, clients.len(), max_main as usize, main_count as usize);
+    let targets = S::targets_for(clients);
+    let (gap, outer_gap) = S::gaps(clients.len());
+    let to_fill = &utils::shrink(to_fill, outer_gap);
+
+    // The main leaves take the first windows, then the fair leaves take the rest in order.
+    let mut next = 0;
+    let mut starts = vec![0; leaves.len()];
+    for kind in &[Leaf::Main, Leaf::Fair] {
+        for (i, (leaf, _)) in leaves.iter().enumerate() {
+            if leaf == kind {
+                starts[i] = next;
+                next += counts[i];
+            }
+        }
+    }
+
+    let mut regions = vec![None; clients.len()];
+    let mut leaf_index = 0;
+    let arrangement = Arrangement { counts: &counts, starts: &starts, targets: &targets, gap };
+    arrangement.arrange::<S>(&description.root, to_fill, ratio, &mut leaf_index, &mut regions);
+
+    let actions = clients.iter().zip(regions).map(|(c, r)| (c.id(), r)).collect();
+    validation::debug_checked(clients, to_fill, gap, actions)
+}
+
+fn collect_leaves(node: &Node, leaves: &mut Vec<(Leaf, Option<usize>)>) {
+    match node {
+        Node::Split(_, parts) => parts.iter().for_each(|(_, part)| collect_leaves(part, leaves)),
+        Node::Leaf(leaf, capacity) => leaves.push((*leaf, *capacity)),
+    }
+}
+
+/// Works out how many windows each leaf gets, when `main_count` of them are in the main region.
+fn share_out(
+    leaves: &[(Leaf, Option<usize>)],
+    window_count: usize,
+    max_main: usize,
+    main_count: usize,
+) -> Vec<usize> {
+    let mut counts = vec![0; leaves.len()];
+    let mut left = window_count;
+    // Only windows in the main region go to the main leaves, even if they have room to spare.
+    let mut main_left = main_count;
+    for kind in &[Leaf::Main, Leaf::Fair] {
+        for (i, (leaf, capacity)) in leaves.iter().enumerate() {
+            let capacity = match (leaf, capacity) {
+                (_, Some(capacity)) => *capacity,
+                (Leaf::Main, None) => max_main,
+                (Leaf::Fair, None) => continue,
+            };
+            if leaf == kind {
+                counts[i] = capacity.min(left);
+                if *leaf == Leaf::Main {
+                    counts[i] = counts[i].min(main_left);
+                    main_left -= counts[i];
+                }
+                left -= counts[i];
+            }
+        }
+    }
+
+    // Then the fair leaves without a capacity share what's left, earlier ones taking any extra.
+    let open: Vec<usize> = (0..leaves.len()).filter(|i| leaves[*i] == (Leaf::Fair, None)).collect();
+    if open.is_empty() {
+        if let Some(last) = counts.last_mut() {
+            *last += left;
+        }
+    } else {
+        for (n, i) in open.iter().enumerate() {
+            counts[*i] = left / open.len() + if n < left % open.len() { 1 } else { 0 };
+        }
+    }
+    counts
+}
+
+/// Everything arranging the tree needs besides the node being arranged.
+struct Arrangement<'a> {
+    counts: &'a [usize],
+    starts: &'a [usize],
+    targets: &'a [Target],
+    gap: u32,
+}
+
+impl<'a> Arrangement<'a> {
+    fn arrange<S: LayoutSettings>(
+        &self,
+        node: &Node,
+        to_fill: &Region,
+        ratio: f32,
+        leaf_index: &mut usize,
+        regions: &mut [Option<Region>],
+    ) {
+        match node {
+            Node::Leaf(leaf, _) => {
+                let (start, count) = (self.starts[*leaf_index], self.counts[*leaf_index]);
+                *leaf_index += 1;
+                if count == 0 {
+                    return;
+                }
+
+                let targets = &self.targets[start..start + count];
+                let layout = match leaf {
+                    Leaf::Main => layout_main_with::<S>(to_fill, targets, self.gap),
+                    Leaf::Fair => layout_region_fairly_with::<S>(to_fill, targets, self.gap),
+                };
+                for (i, r) in layout.into_iter().enumerate() {
+                    regions[start + i] = Some(r);
+                }
+            }
+            Node::Split(direction, parts) => {
+                // Parts without windows give up their space.
+                let mut first_leaf = *leaf_index;
+                let mut shown = Vec::new();
+                for (weight, part) in parts {
+                    let leaf_count = leaf_count(part);
+                    let window_count: usize =
+                        self.counts[first_leaf..first_leaf + leaf_count].iter().sum();
+                    shown.push((*weight, window_count > 0));
+                    first_leaf += leaf_count;
+                }
+
+                let weights = weights(&shown, ratio);
+                let split = match direction {
+                    SplitDirection::LeftRight => utils::split_into_weighted_columns,
+                    SplitDirection::TopBottom => utils::split_into_weighted_rows,
+                };
+                let mut spaces = if weights.is_empty() {
+                    Vec::new()
+                } else {
+                    split(to_fill, &weights, self.gap)
+                }
+                .into_iter();
+
+                for ((_, part), (_, is_shown)) in parts.iter().zip(&shown) {
+                    if *is_shown {
+                        let space = spaces.next().unwrap_or(*to_fill);
+                        self.arrange::<S>(part, &space, ratio, leaf_index, regions);
+                    } else {
+                        *leaf_index += leaf_count(part);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn leaf_count(node: &Node) -> usize {
+    match node {
+        Node::Split(_, parts) => parts.iter().map(|(_, part)| leaf_count(part)).sum(),
+        Node::Leaf(..) => 1,
+    }
+}
+
+/// Turns the weights of the parts being shown into weights for the weighted splits, giving any
+/// part weighted `ratio` exactly that share of the space.
+fn weights(parts: &[(Weight, bool)], ratio: f32) -> Vec<f32> {
+    // Keep every part at least a sliver, however the ratio is set.
+    let ratio = ratio.clamp(0.05, 0.95);
+    let shown = parts.iter().filter(|(_, shown)| *shown).map(|(weight, _)| *weight);
+    let fixed: f32 = shown
+        .clone()
+        .map(|w| match w {
+            Weight::Fixed(w) => w,
+            Weight::Ratio => 0.0,
+        })
+        .sum();
+
+    shown
+        .map(|w| match w {
+            Weight::Fixed(w) => w,
+            Weight::Ratio if fixed > 0.0 => fixed * ratio / (1.0 - ratio),
+            Weight::Ratio => 1.0,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::Widescreen;
+
+    fn clients(count: u32) -> Vec<Client> {
+        (0..count)
+            .map(|i| Client::new(i, "hi".to_string(), "there".to_string(), 7, false))
+            .collect()
+    }
+
+    #[test]
+    fn parses_descriptions() {
+        let description = parse("columns(0.25: fair, ratio: main(1),\n 0.25: fair(2))").unwrap();
+        assert_eq!(
+            description.root,
+            Node::Split(
+                SplitDirection::LeftRight,
+                vec![
+                    (Weight::Fixed(0.25), Node::Leaf(Leaf::Fair, None)),
+                    (Weight::Ratio, Node::Leaf(Leaf::Main, Some(1))),
+                    (Weight::Fixed(0.25), Node::Leaf(Leaf::Fair, Some(2))),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn explains_parse_errors() {
+        let error = |text| parse(text).unwrap_err().to_string();
+        assert_eq!(
+            error("rows(fair,\n  tall)"),
+            "line 2, column 3: unknown part `tall`, expected rows, columns, fair or main"
+        );
+        assert_eq!(error("rows(fair"), "line 1, column 10: expected `)` after the parts of rows");
+        assert_eq!(
+            error("fair(0)"),
+            "line 1, column 6: expected the number of windows fair can hold"
+        );
+        assert_eq!(error("columns(0: fair)"), "line 1, column 9: `0` isn't a positive weight");
+        assert_eq!(
+            error("rows(ratio: main, ratio: fair)"),
+            "line 1, column 19: only one part of rows can take the ratio"
+        );
+        assert_eq!(
+            error("main fair"),
+            "line 1, column 6: unexpected `f` after the end of the layout"
+        );
+
+        let config = parse_config("# layouts\n[ok] = fair\n[bad] = rows(\n");
+        assert_eq!(
+            config.unwrap_err().to_string(),
+            "line 3, column 14: expected rows, columns, fair or main"
+        );
+    }
+
+    #[test]
+    fn shares_out_windows() {
+        let leaves =
+            [(Leaf::Fair, None), (Leaf::Main, None), (Leaf::Fair, Some(2)), (Leaf::Fair, None)];
+        assert_eq!(share_out(&leaves, 1, 2, 1), vec![0, 1, 0, 0]);
+        assert_eq!(share_out(&leaves, 7, 2, 2), vec![2, 2, 2, 1]);
+        assert_eq!(share_out(&[(Leaf::Main, Some(1)), (Leaf::Fair, Some(1))], 4, 2, 1), vec![1, 3]);
+
+        // A demoted window leaves the main region short.
+        assert_eq!(share_out(&leaves, 7, 2, 1), vec![2, 1, 2, 2]);
+    }
+
+    #[test]
+    fn tiles_the_screen() {
+        let description = parse("columns(fair, ratio: rows(main, 0.5: fair(2)), fair)").unwrap();
+        let screen = Region::new(0, 0, 2560, 1440);
+        for count in 1..12 {
+            let clients = clients(count);
+            let refs: Vec<&Client> = clients.iter().collect();
+            let actions = layout::<Widescreen>(&description, &refs, &screen, 2, 0.6);
+            let regions: Vec<Region> = actions.into_iter().filter_map(|(_, r)| r).collect();
+            assert_eq!(validation::validate(&screen, &regions, count as usize, 0), Ok(()));
+        }
+    }
+}
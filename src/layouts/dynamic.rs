@@ -0,0 +1,81 @@
+//! Layout functions built at runtime.
+//!
+//! Penrose layouts are plain function pointers, so a layout built out of another one (eg a
+//! mirrored fair layout) can't capture the layout it wraps.  Instead the closure doing the work is
+//! kept in a table, and the function handed to penrose is one of a fixed set of functions that
+//! each forward to their own entry in that table.
+//!
+
+use std::{cell::RefCell, fmt};
+
+use penrose::{
+    client::Client,
+    data_types::{Region, ResizeAction, WinId},
+    layout::LayoutFunc,
+};
+
+type DynamicLayout = Box<dyn Fn(&[&Client], Option<WinId>, &Region, u32, f32) -> Vec<ResizeAction>>;
+
+thread_local! {
+    // As with the main region, the cat is single threaded, so a thread local is as good as a
+    // global.
+    static LAYOUTS: RefCell<Vec<DynamicLayout>> = RefCell::new(Vec::new());
+}
+
+fn arrange(
+    slot: usize,
+    clients: &[&Client],
+    focused: Option<WinId>,
+    to_fill: &Region,
+    max_main: u32,
+    ratio: f32,
+) -> Vec<ResizeAction> {
+    LAYOUTS.with(|l| (l.borrow()[slot])(clients, focused, to_fill, max_main, ratio))
+}
+
+macro_rules! slots {
+    ($($slot:literal),*) => {
+        const SLOTS: &[LayoutFunc] = &[$(|a, b, c, d, e| arrange($slot, a, b, c, d, e)),*];
+    };
+}
+
+slots!(
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+    26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49,
+    50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63
+);
+
+/// There are only so many layout functions to go around, and they have all been used.  Layouts
+/// are built once at startup, so running out is a configuration mistake.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfSlots;
+
+impl fmt::Display for OutOfSlots {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "only {} layouts can be built at runtime", SLOTS.len())
+    }
+}
+
+impl std::error::Error for OutOfSlots {}
+
+/// Turns a closure into a layout function that penrose can use, unless all of them (64) have
+/// been used.
+pub fn register<F>(f: F) -> Result<LayoutFunc, OutOfSlots>
+where
+    F: Fn(&[&Client], Option<WinId>, &Region, u32, f32) -> Vec<ResizeAction> + 'static,
+{
+    LAYOUTS.with(|l| {
+        let mut l = l.borrow_mut();
+        let slot = l.len();
+        if slot >= SLOTS.len() {
+            return Err(OutOfSlots);
+        }
+        l.push(Box::new(f));
+        Ok(SLOTS[slot])
+    })
+}
+
+/// How many more layout functions can be built before they run out.
+pub fn remaining_slots() -> usize {
+    LAYOUTS.with(|l| SLOTS.len() - l.borrow().len())
+}
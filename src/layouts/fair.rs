@@ -5,49 +5,268 @@
 //! Vertical fair layouts put the lucky windows above one another, and horizontal
 //! fair layouts put them beside one another.
 //!
+//! Tilings that squash windows below their minimum size (see `SizeHints`) are avoided where
+//! possible, and if the tiles would come out smaller than the minimum tile size the windows that
+//! don't fit share an overflow stack (see the overflow module).
+//!
+//! Both variants also consider the best guillotine tiling (see the guillotine module), which can
+//! have rows of unequal heights and leftovers anywhere, and use whichever tiling is the closest
+//! fit.
+//!
 
 use penrose::{
     client::Client,
-    data_types::{Region, ResizeAction},
+    data_types::{Region, ResizeAction, WinId},
     layout::LayoutFunc,
 };
 
-use super::utils;
+use std::{any::TypeId, cell::RefCell, cmp::Ordering, collections::HashMap};
+
+use super::{cost::Cost, guillotine, overflow, utils, validation, LayoutSettings, Target};
+
+/// Creates and returns a closure that performs horizontal fair layouts.
+pub fn new<S: LayoutSettings>() -> LayoutFunc {
+    |a, b, c, _, _| S::fit_windows(a, do_fair_layout::<S>(a, b, c, Lines::Rows))
+}
+
+/// Creates and returns a closure that performs vertical fair layouts.
+pub fn new_vertical<S: LayoutSettings>() -> LayoutFunc {
+    |a, b, c, _, _| S::fit_windows(a, do_fair_layout::<S>(a, b, c, Lines::Columns))
+}
+
+fn do_fair_layout<S: LayoutSettings>(
+    clients: &[&Client],
+    focused: Option<WinId>,
+    to_fill: &Region,
+    lines: Lines,
+) -> Vec<ResizeAction> {
+    let (gap, outer_gap) = S::gaps(clients.len());
+    let to_fill = &utils::shrink(to_fill, outer_gap);
+    let targets = &S::targets_for(clients);
+    let actions =
+        overflow::layout_with_overflow(clients, focused, targets, S::MIN_TILE_SIZE, |targets| {
+            cached::<S>(to_fill, targets, gap, lines)
+        });
+    validation::debug_checked(clients, to_fill, gap, actions)
+}
+
+/// Divides the region into approximately even regions, one per target and `gap` pixels apart,
+/// picking the tiling that suits the windows best by the given cost.
+pub fn layout_region_fairly(
+    to_fill: &Region,
+    targets: &[Target],
+    gap: u32,
+    cost: &dyn Cost,
+) -> Vec<Region> {
+    search(to_fill, targets, gap, cost, Lines::Rows)
+}
+
+/// Like `layout_region_fairly`, but built from columns, so any leftover windows get stacked in
+/// an extra column on the left rather than sharing an extra row along the top.
+pub fn layout_region_fairly_vertically(
+    to_fill: &Region,
+    targets: &[Target],
+    gap: u32,
+    cost: &dyn Cost,
+) -> Vec<Region> {
+    search(to_fill, targets, gap, cost, Lines::Columns)
+}
+
+/// Like `layout_region_fairly` by the settings' cost, but remembers the results, for layouts that
+/// are rerun with the same windows and space.
+pub fn layout_region_fairly_with<S: LayoutSettings>(
+    to_fill: &Region,
+    targets: &[Target],
+    gap: u32,
+) -> Vec<Region> {
+    cached::<S>(to_fill, targets, gap, Lines::Rows)
+}
+
+/// Whether a fair tiling is made of rows of windows or columns of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Lines {
+    Rows,
+    Columns,
+}
+
+/// Everything the result of a search depends on: the region's (x, y, w, h), the gap, the targets'
+/// (aspect ratio bits, min size), the settings (for their cost) and the kind of tiling.
+type CacheKey = ((u32, u32, u32, u32), u32, Vec<(u32, (u32, u32))>, TypeId, Lines);
 
-/// Creates and returns a closure that performs layouts.
-pub fn new() -> LayoutFunc {
-    |a, _, c, _, _| do_fair_layout(a, c)
+/// How many searches to remember before starting afresh.  Each screen and main layout area only
+/// needs one, so this is plenty.
+const CACHE_CAPACITY: usize = 64;
+
+thread_local! {
+    // Layouts are rerun whenever anything changes, even just the focus, but the windows and their
+    // space rarely change between runs, so the search results are remembered.
+    static CACHE: RefCell<HashMap<CacheKey, Vec<Region>>> = RefCell::new(HashMap::new());
 }
 
-fn do_fair_layout(clients: &[&Client], to_fill: &Region) -> Vec<ResizeAction> {
-    let layout = layout_region_fairly(to_fill, clients.len() as u32);
-    clients.iter().zip(layout).map(|(c, r)| (c.id(), Some(r))).collect()
+fn cached<S: LayoutSettings>(
+    to_fill: &Region,
+    targets: &[Target],
+    gap: u32,
+    lines: Lines,
+) -> Vec<Region> {
+    let targets_key = targets.iter().map(|t| (t.aspect_ratio.to_bits(), t.min_size)).collect();
+    let key = (to_fill.values(), gap, targets_key, TypeId::of::<S>(), lines);
+    if let Some(layout) = CACHE.with(|c| c.borrow().get(&key).cloned()) {
+        return layout;
+    }
+
+    let layout = match lines {
+        Lines::Rows => layout_region_fairly(to_fill, targets, gap, S::COST),
+        Lines::Columns => layout_region_fairly_vertically(to_fill, targets, gap, S::COST),
+    };
+    CACHE.with(|c| {
+        let mut c = c.borrow_mut();
+        if c.len() >= CACHE_CAPACITY {
+            c.clear();
+        }
+        c.insert(key, layout.clone());
+    });
+    layout
 }
 
-/// Divides the region into approximately even regions that are each as close as possible to 16:9.
-pub fn layout_region_fairly(to_fill: &Region, window_count: u32) -> Vec<Region> {
-    (0..window_count)
-        .map(|c| layout_region_in_rows(to_fill, window_count, c + 1))
-        .min_by_key(utils::aspect_ratio_sse)
-        .unwrap_or(vec![*to_fill])
+/// Tries every number of full rows (or columns), and then the guillotine tiling, keeping the
+/// best.  The candidates are built in the same two buffers, so trying one doesn't allocate.
+fn search(
+    to_fill: &Region,
+    targets: &[Target],
+    gap: u32,
+    cost: &dyn Cost,
+    lines: Lines,
+) -> Vec<Region> {
+    let window_count = targets.len() as u32;
+    let mut best: Option<(Vec<Region>, (usize, f32))> = None;
+    let mut line_buffer = Vec::with_capacity(targets.len() + 1);
+    let mut candidate = Vec::with_capacity(targets.len());
+
+    // Keeps the better of the candidate and the best so far (ties go to the best so far), and
+    // hands back the other one's buffer to build the next candidate in.
+    let mut consider = |candidate: Vec<Region>| -> Vec<Region> {
+        let candidate = utils::assign_regions(candidate, targets);
+        let score = utils::fair_score(&candidate, targets, cost);
+        let better = match &best {
+            Some((_, best_score)) => score.partial_cmp(best_score) == Some(Ordering::Less),
+            None => true,
+        };
+        if better {
+            best.replace((candidate, score)).map(|(old, _)| old).unwrap_or_default()
+        } else {
+            candidate
+        }
+    };
+
+    for full_line_count in 1..=window_count {
+        candidate.clear();
+        match lines {
+            Lines::Rows => layout_region_in_rows(
+                to_fill,
+                window_count,
+                full_line_count,
+                gap,
+                &mut line_buffer,
+                &mut candidate,
+            ),
+            Lines::Columns => layout_region_in_columns(
+                to_fill,
+                window_count,
+                full_line_count,
+                gap,
+                &mut line_buffer,
+                &mut candidate,
+            ),
+        }
+        candidate = consider(candidate);
+    }
+    // The guillotine tiling goes last so that the simpler tilings win any ties.
+    if !targets.is_empty() {
+        consider(guillotine::layout_region(to_fill, targets, gap, cost));
+    }
+
+    best.map(|(layout, _)| layout).unwrap_or(vec![*to_fill])
 }
 
-fn layout_region_in_rows(to_fill: &Region, window_count: u32, full_row_count: u32) -> Vec<Region> {
+/// Tiles the region in rows, with any leftover windows sharing an extra row along the top, into
+/// `results`.  `rows` is scratch space.
+fn layout_region_in_rows(
+    to_fill: &Region,
+    window_count: u32,
+    full_row_count: u32,
+    gap: u32,
+    rows: &mut Vec<Region>,
+    results: &mut Vec<Region>,
+) {
     let col_count = window_count / full_row_count;
     let top_count = window_count - col_count * full_row_count;
     let row_count = if top_count == 0 { full_row_count } else { full_row_count + 1 };
 
-    let mut results = Vec::new();
+    rows.clear();
+    utils::push_rows(rows, to_fill, row_count, gap);
+    for (i, row) in rows.iter().enumerate() {
+        let count = if i == 0 && top_count > 0 { top_count } else { col_count };
+        utils::push_columns(results, row, count, gap);
+    }
+}
 
-    let mut rows = utils::split_into_rows(to_fill, row_count);
+/// Tiles the region in columns, with any leftover windows sharing an extra column on the left,
+/// into `results`.  `columns` is scratch space.
+fn layout_region_in_columns(
+    to_fill: &Region,
+    window_count: u32,
+    full_column_count: u32,
+    gap: u32,
+    columns: &mut Vec<Region>,
+    results: &mut Vec<Region>,
+) {
+    let row_count = window_count / full_column_count;
+    let left_count = window_count - row_count * full_column_count;
+    let column_count = if left_count == 0 { full_column_count } else { full_column_count + 1 };
 
-    if top_count > 0 {
-        let row = rows.remove(0);
-        results.append(&mut utils::split_into_columns(&row, top_count));
+    columns.clear();
+    utils::push_columns(columns, to_fill, column_count, gap);
+    for (i, column) in columns.iter().enumerate() {
+        let count = if i == 0 && left_count > 0 { left_count } else { row_count };
+        utils::push_rows(results, column, count, gap);
     }
-    for row in rows {
-        results.append(&mut utils::split_into_columns(&row, col_count));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{
+        cost::{AreaVariance, AspectRatioSse, MaxDeviation},
+        validation,
+    };
+    use proptest::prelude::*;
+
+    fn targets(aspect_ratios: &[f32]) -> Vec<Target> {
+        aspect_ratios
+            .iter()
+            .map(|&aspect_ratio| Target { aspect_ratio, min_size: (0, 0) })
+            .collect()
     }
 
-    results
+    proptest! {
+        #[test]
+        fn tiles_the_screen(
+            w in 640u32..7680,
+            h in 480u32..2160,
+            aspect_ratios in prop::collection::vec(0.5f32..3.0, 1..16),
+            gap in 0u32..12,
+        ) {
+            let screen = Region::new(0, 0, w, h);
+            let targets = targets(&aspect_ratios);
+            let costs: [&dyn Cost; 3] = [&AspectRatioSse, &MaxDeviation, &AreaVariance];
+            for layout in [layout_region_fairly, layout_region_fairly_vertically].iter() {
+                for cost in costs.iter() {
+                    let regions = layout(&screen, &targets, gap, *cost);
+                    let n = targets.len();
+                    prop_assert_eq!(validation::validate(&screen, &regions, n, gap), Ok(()));
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,317 @@
+//! A solver for guillotine tilings.
+//!
+//! A guillotine tiling is one that can be made by repeatedly cutting regions clean across, either
+//! horizontally or vertically.  The solver searches them for the tiling that suits the windows
+//! best: each cut can share the windows out any way between its two sides, and either divide the
+//! space fairly (in proportion to the number of windows on each side) or fall on any twelfth of
+//! the way across that gives both sides between half and twice their fair share.  So rows of
+//! unequal heights, and leftover windows above, below or beside the rest, are all in the running.
+//!
+//! The windows are sorted by their target aspect ratio, and each cut gives the first few of a
+//! region's windows to one side and the rest to the other, so windows that want similar shapes end
+//! up together.  Tilings are scored by the layout's cost (see the cost module) one window at a time,
+//! with the scores of the two sides of each cut put together by `Cost::combine`.
+//!
+//! The cost of tiling a region depends (almost) only on its shape and windows, not its size, so
+//! the costs are memoized on those, with shapes rounded to within about two percent.  The rounding
+//! makes those costs estimates, so each region actually cut is given the best cut for its exact
+//! size, by the estimated costs of its parts.  Cuts whose parts are hopelessly out of shape, or
+//! can't beat the best cut found so far, aren't searched any further.  That keeps two dozen windows
+//! that want the same shape to a few milliseconds, but windows that all want different shapes
+//! share less, and two dozen of those take about a third of a second (the fair layout remembers
+//! its results, so that's only when the windows or the screen change).
+//!
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use penrose::data_types::Region;
+
+use super::{cost::Cost, utils, Target};
+
+/// Cuts can fall on any of this many steps of the way across a region, as well as dividing it
+/// fairly.
+const CUT_STEPS: u32 = 12;
+
+/// How far across its region a cut falls, as a fraction (numerator, denominator).
+type Position = (u32, u32);
+
+/// How a region is divided; the count is the number of windows in the top (or left) part.
+#[derive(Clone, Copy, Debug)]
+enum Cut {
+    Leaf,
+    Horizontal(usize, Position),
+    Vertical(usize, Position),
+}
+
+/// Everything the search needs to know besides the region being tiled.
+struct Search<'a> {
+    /// The windows' targets, sorted by aspect ratio.
+    targets: Vec<Target>,
+    /// For each run of windows (start, length), the start of the first run with the same targets,
+    /// so that runs of windows that want the same shapes share their memoized costs.
+    canonical: Vec<Vec<usize>>,
+    cost: &'a dyn Cost,
+    gap: u32,
+    memo: Memo,
+}
+
+/// The (rounded) shape of a region.  Regions without any area are kept apart from the rest, since
+/// their aspect ratio is meaningless.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Shape {
+    Degenerate(u32, u32),
+    Ratio(i32),
+}
+
+impl Shape {
+    fn of(w: u32, h: u32) -> Shape {
+        if w == 0 || h == 0 {
+            Shape::Degenerate(w, h)
+        } else {
+            Shape::Ratio(((w as f32 / h as f32).ln() * 50.0).round() as i32)
+        }
+    }
+}
+
+/// The best (cost, cut) for each (shape, canonical start, window count) seen so far.
+type Memo = HashMap<(Shape, usize, usize), (f32, Cut)>;
+
+/// Finds the guillotine tiling of the region, with `gap` pixels between windows, that (as near as
+/// the search can tell) has the lowest cost for the windows' targets.  The regions come back in
+/// the same order as the targets.
+pub fn layout_region(
+    to_fill: &Region,
+    targets: &[Target],
+    gap: u32,
+    cost: &dyn Cost,
+) -> Vec<Region> {
+    if targets.is_empty() {
+        return Vec::new();
+    }
+
+    let mut order: Vec<usize> = (0..targets.len()).collect();
+    order.sort_by(|&a, &b| {
+        targets[a].aspect_ratio.partial_cmp(&targets[b].aspect_ratio).unwrap_or(Ordering::Equal)
+    });
+    let sorted: Vec<Target> = order.iter().map(|&i| targets[i]).collect();
+    let mut search = Search {
+        canonical: canonical_runs(&sorted),
+        targets: sorted,
+        cost,
+        gap,
+        memo: Memo::new(),
+    };
+
+    let mut built = Vec::with_capacity(targets.len());
+    build(to_fill, 0, targets.len(), &mut search, &mut built);
+    let mut results = vec![*to_fill; targets.len()];
+    for (i, r) in order.into_iter().zip(built) {
+        results[i] = r;
+    }
+    results
+}
+
+/// Works out `Search::canonical` for the sorted targets.
+fn canonical_runs(targets: &[Target]) -> Vec<Vec<usize>> {
+    (0..targets.len())
+        .map(|start| {
+            (0..=targets.len() - start)
+                .map(|len| {
+                    let run = &targets[start..start + len];
+                    (0..=start).find(|&s| &targets[s..s + len] == run).unwrap_or(start)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The (memoized) cost of the best tiling of a region of this size with the run of windows
+/// starting at `start`.
+fn solve(w: u32, h: u32, start: usize, len: usize, search: &mut Search) -> f32 {
+    if len == 1 {
+        // A single window is quicker to score than to look up.
+        return best_cut(w, h, start, len, search).0;
+    }
+    let start = search.canonical[start][len];
+    let key = (Shape::of(w, h), start, len);
+    if let Some((cost, _)) = search.memo.get(&key) {
+        return *cost;
+    }
+
+    let best = best_cut(w, h, start, len, search);
+    search.memo.insert(key, best);
+    best.0
+}
+
+/// Finds the (cost, cut) that best tiles a region of exactly this size with the run of windows
+/// starting at `start`, using the memoized costs of its parts.
+fn best_cut(w: u32, h: u32, start: usize, len: usize, search: &mut Search) -> (f32, Cut) {
+    if len == 1 {
+        let cost = search.cost.cost(&[Region::new(0, 0, w, h)], &search.targets[start..=start]);
+        return (cost, Cut::Leaf);
+    }
+
+    let mut best = (f32::INFINITY, None);
+    // When the windows all want the same shape, cutting off the last few mirrors cutting off the
+    // first few, so only the smaller group is tried, and it goes on top (or to the left) like the
+    // fair layout's remainder.
+    let uniform = search.targets[start..start + len].windows(2).all(|t| t[0] == t[1]);
+    let counts = if uniform { len / 2 } else { len - 1 };
+    // Even shares and cuts across the longer side tend to do best, so they're tried first to give
+    // the bounds in cut_cost the most to rule out.
+    'search: for count in (1..=counts).rev() {
+        for at in positions(count, len) {
+            let (top_h, bottom_h) = cut(h, at, search.gap);
+            let (left_w, right_w) = cut(w, at, search.gap);
+            let mut cuts = [
+                (((w, top_h), (w, bottom_h)), Cut::Horizontal(count, at)),
+                (((left_w, h), (right_w, h)), Cut::Vertical(count, at)),
+            ];
+            if w > h {
+                cuts.swap(0, 1);
+            }
+            for &(parts, made) in &cuts {
+                if let Some(cost) = cut_cost(parts, start, count, len, best.0, search) {
+                    if cost < best.0 {
+                        best = (cost, Some(made));
+                    }
+                }
+                if best.0 <= 0.0 {
+                    // Nothing beats a perfect fit.
+                    break 'search;
+                }
+            }
+        }
+    }
+
+    match best {
+        (cost, Some(cut)) => (cost, cut),
+        (_, None) => {
+            // Too small to cut, so just stack the windows like split_into_rows does.
+            let count = len / 2;
+            let at = (count as u32, len as u32);
+            let (top_h, bottom_h) = cut(h, at, search.gap);
+            let top = solve(w, top_h, start, count, search);
+            let bottom = solve(w, bottom_h, start + count, len - count, search);
+            (search.cost.combine(top, bottom), Cut::Horizontal(count, at))
+        }
+    }
+}
+
+/// The cost of tiling the two (width, height) parts of a cut, the first with `count` of the run's
+/// windows and the second with the rest, unless a part is empty or hopelessly out of shape, or the
+/// cost is bound to be no better than `best`.
+fn cut_cost(
+    ((first_w, first_h), (second_w, second_h)): ((u32, u32), (u32, u32)),
+    start: usize,
+    count: usize,
+    len: usize,
+    best: f32,
+    search: &mut Search,
+) -> Option<f32> {
+    let first_bound = lower_bound(first_w, first_h, start, count, search)?;
+    let second_bound = lower_bound(second_w, second_h, start + count, len - count, search)?;
+    if search.cost.combine(first_bound, second_bound) >= best {
+        return None;
+    }
+    let first = solve(first_w, first_h, start, count, search);
+    if search.cost.combine(first, second_bound) >= best {
+        return None;
+    }
+    let second = solve(second_w, second_h, start + count, len - count, search);
+    Some(search.cost.combine(first, second))
+}
+
+/// A cost that no tiling of a region of this size with the run of windows can beat, or nothing if
+/// it isn't worth tiling at all.
+///
+/// However the region is cut, the windows along any line across it share its width, so one of
+/// them is at least 1/len of the width wide (and at most the full height tall), and likewise one
+/// is at least 1/len of the height tall.  If even those are more than twice as wide (or tall) as
+/// the windows want, the region is hopelessly out of shape.
+fn lower_bound(w: u32, h: u32, start: usize, len: usize, search: &Search) -> Option<f32> {
+    if w == 0 || h == 0 {
+        return None;
+    }
+    // The targets are sorted, so the last window wants to be the widest and the first the tallest.
+    let (tallest, widest) = (search.targets[start], search.targets[start + len - 1]);
+    let wide = Region::new(0, 0, (w / len as u32).max(1), h);
+    let tall = Region::new(0, 0, w, (h / len as u32).max(1));
+    let (wide_ratio, tall_ratio) = (utils::aspect_ratio(&wide), utils::aspect_ratio(&tall));
+    if wide_ratio > 2.0 * widest.aspect_ratio || tall_ratio < tallest.aspect_ratio / 2.0 {
+        return None;
+    }
+
+    // That assumes, like the costs that come with the cat, that a window costs more the further
+    // it is from its target.
+    let too_wide =
+        if wide_ratio > widest.aspect_ratio { search.cost.cost(&[wide], &[widest]) } else { 0.0 };
+    let too_tall =
+        if tall_ratio < tallest.aspect_ratio { search.cost.cost(&[tall], &[tallest]) } else { 0.0 };
+    Some(too_wide.max(too_tall))
+}
+
+/// Where a cut giving `count` of `len` windows to its first side can fall: fairly, then on each
+/// step of the way across that leaves both sides between half and twice their fair share (any
+/// further out would be no kind of fair).  Fair cuts come first so that they win any ties, eg with
+/// costs that only care about area.
+fn positions(count: usize, len: usize) -> impl Iterator<Item = Position> {
+    let (count, len) = (count as u32, len as u32);
+    // Whether a share of n/d is within a factor of two of a fair share of c/l.
+    let near = |n: u32, d: u32, c: u32, l: u32| 2 * n * l >= c * d && n * l <= 2 * c * d;
+    let steps = (1..CUT_STEPS).filter(move |&step| {
+        step * len != CUT_STEPS * count
+            && near(step, CUT_STEPS, count, len)
+            && near(CUT_STEPS - step, CUT_STEPS, len - count, len)
+    });
+    std::iter::once((count, len)).chain(steps.map(|step| (step, CUT_STEPS)))
+}
+
+fn build(r: &Region, start: usize, len: usize, search: &mut Search, results: &mut Vec<Region>) {
+    let (_, _, w, h) = r.values();
+    match best_cut(w, h, start, len, search).1 {
+        Cut::Leaf => results.push(*r),
+        Cut::Horizontal(count, at) => {
+            let (top_h, _) = cut(h, at, search.gap);
+            let (top, bottom) = utils::split_at_height(r, top_h, search.gap);
+            build(&top, start, count, search, results);
+            build(&bottom, start + count, len - count, search, results);
+        }
+        Cut::Vertical(count, at) => {
+            let (left_w, _) = cut(w, at, search.gap);
+            let (left, right) = utils::split_at_width(r, left_w, search.gap);
+            build(&left, start, count, search, results);
+            build(&right, start + count, len - count, search, results);
+        }
+    }
+}
+
+/// Splits a length at the given position, leaving a gap between the two parts.
+fn cut(length: u32, (numerator, denominator): Position, gap: u32) -> (u32, u32) {
+    let first = length.saturating_sub(gap) * numerator / denominator;
+    (first, length.saturating_sub(first + gap))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::cost::{AreaVariance, AspectRatioSse};
+
+    fn target(aspect_ratio: f32) -> Target {
+        Target { aspect_ratio, min_size: (0, 0) }
+    }
+
+    #[test]
+    fn cuts_unequally_for_different_targets() {
+        let screen = Region::new(0, 0, 2500, 900);
+        let targets = [target(4.0 / 3.0), target(16.0 / 9.0)];
+
+        // The narrower window gets the narrower column.
+        let layout = layout_region(&screen, &targets, 0, &AspectRatioSse);
+        assert_eq!(layout, vec![Region::new(0, 0, 1041, 900), Region::new(1041, 0, 1459, 900)]);
+
+        // Unless all that matters is area.
+        let layout = layout_region(&screen, &targets, 0, &AreaVariance);
+        assert_eq!(layout, vec![Region::new(0, 0, 1250, 900), Region::new(1250, 0, 1250, 900)]);
+    }
+}
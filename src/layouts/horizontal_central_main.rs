@@ -1,9 +1,18 @@
 //! The horizontal central main layout has a large central area for your most important
 //! window(s) and equally sized side areas for others.
 //!
-//! Within each of the three areas, windows are tiled to remain approximately 4:3, with ties
-//! broken in favor of (by which I mean more space given to) the first window(s) in the area.
+//! Windows stay in the main area until they are demoted (see the main_region module), rather
+//! than being pushed out whenever a new window opens.
 //!
+//! On very wide screens the main area is kept from growing much wider than its windows want to be,
+//! with the rest of the space going to the side areas.
+//!
+//! Within each of the three areas, windows are tiled to remain approximately the target aspect
+//! ratio, with ties broken in favor of (by which I mean more space given to) the first window(s)
+//! in the area.
+//!
+
+use std::cmp::Ordering;
 
 use penrose::{
     client::Client,
@@ -11,123 +20,256 @@ use penrose::{
     layout::LayoutFunc,
 };
 
-use super::{fair::layout_region_fairly, utils, TARGET_ASPECT_RATIO};
+use super::{
+    cost::Cost,
+    fair::{layout_region_fairly, layout_region_fairly_with},
+    main_region, utils, validation, FillOrder, LayoutSettings, Target,
+};
 
 /// Creates and returns a closure that performs layouts.
-pub fn new() -> LayoutFunc {
-    |a, _b, c, d, e| do_horizontal_central_main_layout(a, c, d, e)
+pub fn new<S: LayoutSettings>() -> LayoutFunc {
+    |a, _b, c, d, e| S::fit_windows(a, do_horizontal_central_main_layout::<S>(a, c, d, e))
 }
 
-fn do_horizontal_central_main_layout(
+fn do_horizontal_central_main_layout<S: LayoutSettings>(
     clients: &[&Client],
     to_fill: &Region,
     main_region_window_count: u32,
     main_region_ratio: f32,
 ) -> Vec<ResizeAction> {
+    // Demoted windows can leave the main region short of its full count.
+    let (clients, main_region_window_count) =
+        main_region::main_first(clients, main_region_window_count);
+    let clients = &clients;
+    let targets = &S::targets_for(clients);
+    let (gap, outer_gap) = S::gaps(clients.len());
+    let to_fill = &utils::shrink(to_fill, outer_gap);
+
     if main_region_window_count >= clients.len() as u32 {
-        do_all_main_layout(clients, to_fill)
+        do_all_main_layout::<S>(clients, to_fill, targets, gap)
     } else if main_region_window_count + 1 == clients.len() as u32 {
-        do_two_region_layout(clients, to_fill, main_region_window_count, main_region_ratio)
+        do_two_region_layout::<S>(
+            clients,
+            to_fill,
+            main_region_window_count,
+            main_region_ratio,
+            targets,
+            gap,
+        )
     } else {
-        do_three_region_layout(clients, to_fill, main_region_window_count, main_region_ratio)
+        do_three_region_layout::<S>(
+            clients,
+            to_fill,
+            main_region_window_count,
+            main_region_ratio,
+            targets,
+            gap,
+        )
     }
 }
 
-fn region_widths(to_fill: &Region, main_region_ratio: f32) -> (u32, u32) {
+fn region_widths(
+    to_fill: &Region,
+    main_region_ratio: f32,
+    max_main_width: u32,
+    gap: u32,
+) -> (u32, u32) {
     let (_, _, w, _) = to_fill.values();
-    let m = (w as f32 * main_region_ratio).ceil() as u32;
+    // Leave room for the gaps either side of the main region.
+    let w = w.saturating_sub(2 * gap);
+    let m = ((w as f32 * main_region_ratio).ceil() as u32).min(max_main_width);
 
     // The non-main area must be even so it can be cut in half.
-    let m = if (w - m) % 2 == 1 { m - 1 } else { m };
+    let m = if (w - m) % 2 == 1 { m.saturating_sub(1) } else { m };
 
     (m, (w - m) / 2)
 }
 
-fn layout_main(to_fill: &Region, window_count: u32) -> Vec<Region> {
-    match window_count {
+/// How many times wider than its windows want the screen has to be before the main region is
+/// capped.  Screens only a little wider than that (eg 16:9 windows on a 16:9 screen with a bar)
+/// would otherwise get slivers of side areas.
+pub const MAX_MAIN_SLACK: f32 = 1.2;
+
+/// On very wide (eg 21:9 or 32:9) screens even a modest share of the width can make the main
+/// region absurdly wide, so it is capped at the width of its windows sitting side by side at
+/// their target aspect ratios.  On ordinary screens there is no cap.
+fn max_main_width(to_fill: &Region, main_targets: &[Target], gap: u32) -> u32 {
+    let (_, _, w, h) = to_fill.values();
+    let gaps = gap * (main_targets.len() as u32).saturating_sub(1);
+    let wanted =
+        (h as f32 * main_targets.iter().map(|t| t.aspect_ratio).sum::<f32>()) as u32 + gaps;
+    if w as f32 > wanted as f32 * MAX_MAIN_SLACK {
+        wanted
+    } else {
+        u32::MAX
+    }
+}
+
+/// Lays out the windows of a main region, giving the first window the lion's share if there are
+/// only two of them (unless the region is wide enough to give them both a good shape side by
+/// side, or tall enough to give them one above the other).  Splits are scored by the given cost.
+pub fn layout_main(to_fill: &Region, targets: &[Target], gap: u32, cost: &dyn Cost) -> Vec<Region> {
+    match targets.len() {
         0 => Vec::new(),
         1 => vec![*to_fill],
         2 => {
             let (_, _, w, h) = to_fill.values();
-            let floor = h * 3 / 4;
-            let (row_a, row_b) = to_fill.split_at_height(floor);
-            let wall = w * 2 / 3;
-            let (col_a, col_b) = to_fill.split_at_width(wall);
+            let wall = w.saturating_sub(gap) * 2 / 3;
+            let (col_a, col_b) = utils::split_at_width(to_fill, wall, gap);
+            let floor = h.saturating_sub(gap) * 3 / 4;
+            let (row_a, row_b) = utils::split_at_height(to_fill, floor, gap);
 
-            let row_ratio = utils::aspect_ratio(&row_a);
-            let col_ratio = utils::aspect_ratio(&col_a);
+            // Don't squash either window below its minimum size if another split wouldn't, and
+            // otherwise go with whichever split suits the windows best (ties go to the columns).
+            let score = |layout: &[Region]| {
+                (utils::min_size_violations(layout, targets), cost.cost(layout, targets))
+            };
+            vec![
+                vec![col_a, col_b],
+                vec![row_a, row_b],
+                utils::split_into_columns(to_fill, 2, gap),
+                utils::split_into_rows(to_fill, 2, gap),
+            ]
+            .into_iter()
+            .min_by(|a, b| score(a).partial_cmp(&score(b)).unwrap_or(Ordering::Equal))
+            .unwrap_or_default()
+        }
+        _ => layout_region_fairly(to_fill, targets, gap, cost),
+    }
+}
 
-            if (TARGET_ASPECT_RATIO - row_ratio).abs() < (TARGET_ASPECT_RATIO - col_ratio).abs() {
-                vec![row_a, row_b]
-            } else {
-                vec![col_a, col_b]
-            }
+/// Like `layout_main` by the settings' cost, but remembers the fair tilings of bigger main
+/// regions (see `layout_region_fairly_with`).
+pub fn layout_main_with<S: LayoutSettings>(
+    to_fill: &Region,
+    targets: &[Target],
+    gap: u32,
+) -> Vec<Region> {
+    if targets.len() > 2 {
+        layout_region_fairly_with::<S>(to_fill, targets, gap)
+    } else {
+        layout_main(to_fill, targets, gap, S::COST)
+    }
+}
+
+/// Works out which of the windows outside the main region go to the left (first) and right
+/// (second) areas, as indices into those windows.  The left area gets half of the windows,
+/// rounded down.
+pub fn split_peripheral(count: usize, fill_order: FillOrder) -> (Vec<usize>, Vec<usize>) {
+    let left_count = count / 2;
+    match fill_order {
+        FillOrder::Alternating => (0..count).partition(|i| i % 2 == 1),
+        FillOrder::LeftFirst => ((0..left_count).collect(), (left_count..count).collect()),
+        FillOrder::RightFirst => {
+            ((count - left_count..count).collect(), (0..count - left_count).collect())
         }
-        _ => layout_region_fairly(to_fill, window_count),
     }
 }
 
-fn do_all_main_layout(clients: &[&Client], to_fill: &Region) -> Vec<ResizeAction> {
-    let layout = layout_main(to_fill, clients.len() as u32);
+fn do_all_main_layout<S: LayoutSettings>(
+    clients: &[&Client],
+    to_fill: &Region,
+    targets: &[Target],
+    gap: u32,
+) -> Vec<ResizeAction> {
+    let (x, y, w, h) = to_fill.values();
+    let main_w = w.min(max_main_width(to_fill, targets, gap));
+    let main = Region::new(x + (w - main_w) / 2, y, main_w, h);
+    let layout = layout_main_with::<S>(&main, targets, gap);
 
-    clients.iter().zip(layout).map(|(c, r)| (c.id(), Some(r))).collect()
+    // Only the (possibly capped) main region is tiled, the space either side is left empty.
+    let actions = clients.iter().zip(layout).map(|(c, r)| (c.id(), Some(r))).collect();
+    validation::debug_checked(clients, &main, gap, actions)
 }
 
-fn do_two_region_layout(
+fn do_two_region_layout<S: LayoutSettings>(
     clients: &[&Client],
     to_fill: &Region,
     main_region_window_count: u32,
     main_region_ratio: f32,
+    targets: &[Target],
+    gap: u32,
 ) -> Vec<ResizeAction> {
     // 2/3rds here to account for the main window also getting the left column.
     let main_region_ratio = main_region_ratio * 2.0 / 3.0;
-    let (main_w, secondary_w) = region_widths(to_fill, main_region_ratio);
-    let (main, secondary) = to_fill.split_at_width(main_w + secondary_w);
-    let main_layout = layout_main(&main, main_region_window_count);
-    let secondary_layout =
-        layout_region_fairly(&secondary, clients.len() as u32 - main_region_window_count);
+    let (main_targets, secondary_targets) = targets.split_at(main_region_window_count as usize);
+    let (main_w, secondary_w) = region_widths(to_fill, main_region_ratio, u32::MAX, gap);
+    let main_w = (main_w + secondary_w + gap).min(max_main_width(to_fill, main_targets, gap));
+    let (main, secondary) = utils::split_at_width(to_fill, main_w, gap);
+    let main_layout = layout_main_with::<S>(&main, main_targets, gap);
+    let secondary_layout = layout_region_fairly_with::<S>(&secondary, secondary_targets, gap);
 
-    clients
+    let actions = clients
         .iter()
         .zip(main_layout.into_iter().chain(secondary_layout))
         .map(|(c, r)| (c.id(), Some(r)))
-        .collect()
+        .collect();
+    validation::debug_checked(clients, to_fill, gap, actions)
 }
 
-fn do_three_region_layout(
+fn do_three_region_layout<S: LayoutSettings>(
     clients: &[&Client],
     to_fill: &Region,
     main_region_window_count: u32,
     main_region_ratio: f32,
+    targets: &[Target],
+    gap: u32,
 ) -> Vec<ResizeAction> {
-    let (main_w, secondary_w) = region_widths(to_fill, main_region_ratio);
-    let (left, remainder) = to_fill.split_at_width(secondary_w);
-    let (main, right) = remainder.split_at_width(main_w);
-
-    let left_window_count = (clients.len() as u32 - main_region_window_count) / 2;
-    let left_layout = layout_region_fairly(&left, left_window_count);
-    let main_layout = layout_main(&main, main_region_window_count);
-    let right_layout = layout_region_fairly(
-        &right,
-        clients.len() as u32 - left_window_count - main_region_window_count,
-    );
-
-    clients
+    let (main_clients, others) = clients.split_at(main_region_window_count as usize);
+    let (main_targets, other_targets) = targets.split_at(main_region_window_count as usize);
+
+    let max_main_w = max_main_width(to_fill, main_targets, gap);
+    let (main_w, secondary_w) = region_widths(to_fill, main_region_ratio, max_main_w, gap);
+    let (left, remainder) = utils::split_at_width(to_fill, secondary_w, gap);
+    let (main, right) = utils::split_at_width(&remainder, main_w, gap);
+    let (left_indices, right_indices) = split_peripheral(others.len(), S::FILL_ORDER);
+    let pick = |indices: &[usize]| indices.iter().map(|i| other_targets[*i]).collect::<Vec<_>>();
+
+    let main_layout = layout_main_with::<S>(&main, main_targets, gap);
+    let left_layout = layout_region_fairly_with::<S>(&left, &pick(&left_indices), gap);
+    let right_layout = layout_region_fairly_with::<S>(&right, &pick(&right_indices), gap);
+
+    let actions = main_clients
         .iter()
-        .zip(left_layout.into_iter().chain(main_layout).chain(right_layout))
+        .zip(main_layout)
+        .chain(left_indices.iter().map(|i| &others[*i]).zip(left_layout))
+        .chain(right_indices.iter().map(|i| &others[*i]).zip(right_layout))
         .map(|(c, r)| (c.id(), Some(r)))
-        .collect()
+        .collect();
+    validation::debug_checked(clients, to_fill, gap, actions)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::layouts::{
+        cost::AspectRatioSse, fair::layout_region_fairly, validation, Widescreen,
+    };
+    use proptest::prelude::*;
+
+    struct Gapped;
+
+    impl LayoutSettings for Gapped {
+        const INNER_GAP_PX: u32 = 6;
+        const OUTER_GAP_PX: u32 = 3;
+    }
+
+    fn clients(count: usize) -> Vec<Client> {
+        (0..count)
+            .map(|i| Client::new(i as u32, "hi".to_string(), "there".to_string(), 1, false))
+            .collect()
+    }
+
+    fn regions(actions: Vec<ResizeAction>) -> Vec<Region> {
+        actions.into_iter().filter_map(|(_, r)| r).collect()
+    }
 
     #[test]
     fn fullscreens_single_client() {
         let client = Client::new(0, "hi".to_string(), "there".to_string(), 1, false);
         let screen = Region::new(0, 0, 1920, 1200);
-        let results = do_horizontal_central_main_layout(&vec![&client], None, &screen, 1, 0.667);
+        let results =
+            do_horizontal_central_main_layout::<Widescreen>(&[&client], &screen, 1, 0.667);
 
         println!("{:?}", results);
         assert_eq!(results.len(), 1);
@@ -137,10 +279,45 @@ mod tests {
     #[test]
     fn divides_columns_into_rows() {
         let screen = Region::new(0, 0, 480, 1200);
-        let results = layout_region(&screen, 2);
+        let target = Target { aspect_ratio: 16.0 / 9.0, min_size: (0, 0) };
+        let results = layout_region_fairly(&screen, &[target; 2], 0, &AspectRatioSse);
         println!("{:?}", results);
         assert_eq!(results.len(), 2);
         assert_eq!(results[0], Region::new(0, 0, 480, 600));
         assert_eq!(results[1], Region::new(0, 600, 480, 600));
     }
+
+    proptest! {
+        #[test]
+        fn tiles_the_screen(
+            w in 640u32..7680,
+            h in 480u32..2160,
+            count in 1usize..16,
+            main in 0u32..5,
+            ratio in 0.2f32..0.8,
+        ) {
+            let clients = clients(count);
+            let refs: Vec<&Client> = clients.iter().collect();
+            let screen = Region::new(0, 0, w, h);
+
+            let layout =
+                do_horizontal_central_main_layout::<Widescreen>(&refs, &screen, main, ratio);
+            // With more than the main windows the whole screen is used, otherwise the main
+            // region may be narrowed on wide screens.
+            if count > main as usize {
+                prop_assert_eq!(validation::validate(&screen, &regions(layout), count, 0), Ok(()));
+            } else {
+                prop_assert_eq!(regions(layout).len(), count);
+            }
+
+            let layout = do_horizontal_central_main_layout::<Gapped>(&refs, &screen, main, ratio);
+            let inner = utils::shrink(&screen, Gapped::OUTER_GAP_PX);
+            if count > main as usize {
+                let gap = Gapped::INNER_GAP_PX;
+                prop_assert_eq!(validation::validate(&inner, &regions(layout), count, gap), Ok(()));
+            } else {
+                prop_assert_eq!(regions(layout).len(), count);
+            }
+        }
+    }
 }
@@ -0,0 +1,216 @@
+//! Keeps track of which windows occupy the main region of each workspace.
+//!
+//! Left to their own devices layouts fill the main region with the first windows on the
+//! workspace, so opening a new window shoves whatever you were working on out of the main region.
+//! Instead the cat remembers which windows are in the main region, and only changes that when it
+//! has to (eg a main window closes or the main region grows) or when asked to with `promote` and
+//! `demote`.  Demoted windows are kept out of the main region, even if it has room to spare,
+//! until the next promotion.
+//!
+
+use std::{cell::RefCell, collections::HashMap};
+
+use penrose::{client::Client, core::ring::Selector, data_types::WinId, WindowManager};
+
+/// What the cat remembers about the main region of a workspace.
+#[derive(Clone, Debug, Default)]
+struct MainRegion {
+    /// The windows in the main region, in order.
+    main: Vec<WinId>,
+    /// The windows that have been demoted since the last promotion.
+    demoted: Vec<WinId>,
+}
+
+thread_local! {
+    // Layouts are plain functions, so this can't live in them.  The cat is single threaded, so a
+    // thread local is as good as a global.
+    static MAIN_CLIENTS: RefCell<HashMap<usize, MainRegion>> = RefCell::new(HashMap::new());
+
+    // All of the windows on the workspace, while layouts are given only some of them (see
+    // `laying_out_parts`).
+    static WHOLE_WORKSPACE: RefCell<Option<Vec<WinId>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, which hands parts of the clients to other layouts (eg a split layout does), without
+/// those layouts dropping the windows in the other parts from the main region.
+pub(super) fn laying_out_parts<T>(clients: &[&Client], f: impl FnOnce() -> T) -> T {
+    // When parts are split up again, the outermost layout still has the whole workspace.
+    let outer = WHOLE_WORKSPACE.with(|w| w.borrow().clone());
+    let whole = outer.clone().unwrap_or_else(|| clients.iter().map(|c| c.id()).collect());
+    WHOLE_WORKSPACE.with(|w| *w.borrow_mut() = Some(whole));
+    let result = f();
+    WHOLE_WORKSPACE.with(|w| *w.borrow_mut() = outer);
+    result
+}
+
+/// Reorders the clients of a workspace so that the ones in its main region come first, topping
+/// the main region up from the remaining clients (in order) if it has room to spare.  Also
+/// returns how many clients are in the main region, which can be fewer than `max_main` when
+/// windows have been demoted.
+pub fn main_first<'a>(clients: &[&'a Client], max_main: u32) -> (Vec<&'a Client>, u32) {
+    let workspace = match clients.first() {
+        Some(c) => c.workspace(),
+        None => return (Vec::new(), 0),
+    };
+    let ids: Vec<WinId> = clients.iter().map(|c| c.id()).collect();
+    let whole = WHOLE_WORKSPACE.with(|w| w.borrow().clone()).unwrap_or_else(|| ids.clone());
+
+    MAIN_CLIENTS.with(|m| {
+        let mut m = m.borrow_mut();
+        let region = m.entry(workspace).or_default();
+        top_up(region, &ids, &whole, max_main);
+
+        // Some of the main region may be in another part of the workspace.
+        let main = &region.main;
+        let mut results: Vec<&Client> =
+            main.iter().filter_map(|id| clients.iter().find(|c| c.id() == *id)).copied().collect();
+        let main_count = results.len() as u32;
+        results.extend(clients.iter().filter(|c| !main.contains(&c.id())));
+        (results, main_count)
+    })
+}
+
+/// Like `main_first`, but for window ids and without updating what is in the main region, for
+/// things (eg bars) that want to show the windows the way the layout will arrange them.
+pub fn peek_main_first(workspace: usize, ids: &[WinId], max_main: u32) -> (Vec<WinId>, usize) {
+    let mut region = MAIN_CLIENTS.with(|m| m.borrow().get(&workspace).cloned().unwrap_or_default());
+    top_up(&mut region, ids, ids, max_main);
+
+    let mut results = region.main.clone();
+    results.extend(ids.iter().filter(|id| !region.main.contains(id)));
+    (results, region.main.len())
+}
+
+/// Drops any windows that have left the main region (or the `whole` workspace), then fills it
+/// back up from `ids` in order, skipping demoted windows.
+fn top_up(region: &mut MainRegion, ids: &[WinId], whole: &[WinId], max_main: u32) {
+    let MainRegion { main, demoted } = region;
+    main.retain(|id| whole.contains(id));
+    main.truncate(max_main as usize);
+    demoted.retain(|id| whole.contains(id));
+    for id in ids {
+        if main.len() >= max_main as usize {
+            break;
+        }
+        if !main.contains(id) && !demoted.contains(id) {
+            main.push(*id);
+        }
+    }
+}
+
+/// Moves the focused window into the main region (at the front), bumping the last main window
+/// out if the main region is full.  Any demoted windows may be used to fill the main region again.
+pub fn promote(wm: &mut WindowManager) {
+    if let Some((id, workspace, max_main)) = focused(wm) {
+        MAIN_CLIENTS.with(|m| {
+            let mut m = m.borrow_mut();
+            let region = m.entry(workspace).or_default();
+            region.demoted.clear();
+            region.main.retain(|i| *i != id);
+            region.main.insert(0, id);
+            region.main.truncate(max_main as usize);
+        });
+        wm.layout_screen(wm.active_screen_index());
+    }
+}
+
+/// Moves the focused window out of the main region, replacing it with the first window that
+/// isn't already in the main region (or demoted).  It stays out until the next promotion.
+pub fn demote(wm: &mut WindowManager) {
+    if let Some((id, workspace, max_main)) = focused(wm) {
+        let order: Vec<WinId> = match wm.workspace(&Selector::Index(workspace)) {
+            Some(ws) => ws.iter().copied().collect(),
+            None => return,
+        };
+
+        MAIN_CLIENTS.with(|m| {
+            let mut m = m.borrow_mut();
+            let MainRegion { main, demoted } = m.entry(workspace).or_default();
+            if main.contains(&id) {
+                main.retain(|i| *i != id);
+                demoted.push(id);
+                let next = order.iter().find(|i| !main.contains(i) && !demoted.contains(i));
+                if let Some(next) = next {
+                    if main.len() < max_main as usize {
+                        main.push(*next);
+                    }
+                }
+            }
+        });
+        wm.layout_screen(wm.active_screen_index());
+    }
+}
+
+/// The id and workspace of the focused window, along with the size of that workspace's main
+/// region.
+fn focused(wm: &WindowManager) -> Option<(WinId, usize, u32)> {
+    let client = wm.client(&Selector::Focused)?;
+    let workspace = client.workspace();
+    let max_main = wm.workspace(&Selector::Index(workspace))?.get_max_main();
+    Some((client.id(), workspace, max_main))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{
+        horizontal_central_main, split, SplitClients, SplitDirection, Widescreen,
+    };
+    use penrose::data_types::Region;
+
+    #[test]
+    fn keeps_demoted_windows_out() {
+        let mut region = MainRegion { main: vec![2], demoted: vec![1] };
+        top_up(&mut region, &[1, 2, 3], &[1, 2, 3], 2);
+        assert_eq!(region.main, vec![2, 3]);
+
+        // Even when there is nothing else to fill the main region with.
+        let mut region = MainRegion { main: vec![2], demoted: vec![1] };
+        top_up(&mut region, &[1, 2], &[1, 2], 2);
+        assert_eq!(region.main, vec![2]);
+
+        // Until the window closes.
+        top_up(&mut region, &[2], &[2], 2);
+        assert!(region.demoted.is_empty());
+    }
+
+    #[test]
+    fn layouts_keep_demoted_windows_out() {
+        let clients: Vec<Client> = (1..=3)
+            .map(|i| Client::new(i, "hi".to_string(), "there".to_string(), 1, false))
+            .collect();
+        let clients: Vec<&Client> = clients.iter().collect();
+        MAIN_CLIENTS.with(|m| {
+            m.borrow_mut().insert(1, MainRegion { main: vec![2], demoted: vec![1, 3] });
+        });
+
+        // Window 2 has the main region (in the middle) to itself, even though it has room for two.
+        let (ordered, main_count) = main_first(&clients, 2);
+        assert_eq!(ordered.iter().map(|c| c.id()).collect::<Vec<_>>(), vec![2, 1, 3]);
+        assert_eq!(main_count, 1);
+
+        let screen = Region::new(0, 0, 3840, 1080);
+        let layout = horizontal_central_main::new::<Widescreen>();
+        let actions = layout(&clients, None, &screen, 2, 0.6);
+        let x = |id: WinId| actions.iter().find(|(i, _)| *i == id).unwrap().1.unwrap().values().0;
+        assert!(x(1).min(x(3)) < x(2) && x(2) < x(1).max(x(3)));
+    }
+
+    #[test]
+    fn split_layouts_keep_the_main_region() {
+        let clients: Vec<Client> = (0..6)
+            .map(|i| Client::new(i, "hi".to_string(), "there".to_string(), 1, false))
+            .collect();
+        let clients: Vec<&Client> = clients.iter().collect();
+        MAIN_CLIENTS.with(|m| {
+            m.borrow_mut().insert(1, MainRegion { main: vec![5], demoted: vec![] });
+        });
+
+        // The first part doesn't have window 5, but it is still on the workspace.
+        let hcm = horizontal_central_main::new::<Widescreen>();
+        let layout = split(SplitDirection::LeftRight, 0.5, SplitClients::First(3), hcm, hcm);
+        let screen = Region::new(0, 0, 3840, 1080);
+        layout.unwrap()(&clients, None, &screen, 1, 0.6);
+        assert_eq!(MAIN_CLIENTS.with(|m| m.borrow()[&1].main.clone()), vec![5]);
+    }
+}
@@ -0,0 +1,178 @@
+//! Keeps tiles from getting too small to be useful.
+//!
+//! When giving every window a tile would make the tiles smaller than the minimum tile size (see
+//! `LayoutSettings::MIN_TILE_SIZE`), the windows that don't fit are collapsed into an overflow
+//! stack instead.  The stack takes a single tile, showing the most recently focused of its windows,
+//! and the rest of the stack is unmapped until it is focused.  The window list widget marks the
+//! stacked windows so they don't get lost.
+//!
+
+use std::{cell::RefCell, collections::HashMap};
+
+use penrose::{
+    client::Client,
+    data_types::{Region, ResizeAction, WinId},
+};
+
+use super::Target;
+
+/// The windows in a workspace's overflow stack, and the one being shown.
+struct Stack {
+    windows: Vec<WinId>,
+    shown: WinId,
+    /// Whether the stack was made by the layout that is running (see `settle`).
+    fresh: bool,
+}
+
+thread_local! {
+    // Like the main region, the stacks have to outlive the (plain function) layouts.
+    static STACKS: RefCell<HashMap<usize, Stack>> = RefCell::new(HashMap::new());
+}
+
+/// Lists the windows in the workspace's overflow stack (if it has one).
+pub fn stacked(workspace: usize) -> Vec<WinId> {
+    STACKS.with(|s| s.borrow().get(&workspace).map(|s| s.windows.clone()).unwrap_or_default())
+}
+
+/// Forgets the stacked windows that have just been laid out by a layout that doesn't stack them (eg
+/// after switching layouts), so the window list stops marking them.  Every layout calls this as it
+/// finishes (see `LayoutSettings::fit_windows`), including the ones that did the stacking.
+pub fn settle(clients: &[&Client]) {
+    let workspace = match clients.first() {
+        Some(c) => c.workspace(),
+        None => return,
+    };
+    STACKS.with(|s| {
+        let mut s = s.borrow_mut();
+        match s.get_mut(&workspace) {
+            Some(stack) if stack.fresh => stack.fresh = false,
+            Some(_) => unstack(&mut s, workspace, clients),
+            None => {}
+        }
+    });
+}
+
+/// Takes the clients out of the workspace's stack, dropping the stack if the window it shows is
+/// one of them.  Windows that aren't among the clients stay stacked, since a layout may only have
+/// been given some of the workspace's windows (eg by a split layout).
+fn unstack(stacks: &mut HashMap<usize, Stack>, workspace: usize, clients: &[&Client]) {
+    if let Some(stack) = stacks.get_mut(&workspace) {
+        stack.windows.retain(|id| clients.iter().all(|c| c.id() != *id));
+        if !stack.windows.contains(&stack.shown) {
+            stacks.remove(&workspace);
+        }
+    }
+}
+
+/// Tiles as many of the clients as `layout` can fit into tiles at least `min_size`, and stacks the
+/// rest in the last tile.
+pub fn layout_with_overflow<F>(
+    clients: &[&Client],
+    focused: Option<WinId>,
+    targets: &[Target],
+    min_size: (u32, u32),
+    layout: F,
+) -> Vec<ResizeAction>
+where
+    F: Fn(&[Target]) -> Vec<Region>,
+{
+    let workspace = match clients.first() {
+        Some(c) => c.workspace(),
+        None => return Vec::new(),
+    };
+    let fits = |tiles: &[Region]| {
+        tiles.iter().all(|r| {
+            let (_, _, w, h) = r.values();
+            w >= min_size.0 && h >= min_size.1
+        })
+    };
+
+    let everyone = layout(targets);
+    if fits(&everyone) || clients.len() == 1 {
+        STACKS.with(|s| unstack(&mut s.borrow_mut(), workspace, clients));
+        return clients.iter().zip(everyone).map(|(c, r)| (c.id(), Some(r))).collect();
+    }
+
+    // Fewer tiles means bigger tiles, so binary search for the most tiles that fit.  One tile
+    // always "fits", there's nothing smaller to fall back to.
+    let (mut fitting, mut too_many) = (1, clients.len());
+    while too_many - fitting > 1 {
+        let tiles = (fitting + too_many) / 2;
+        if fits(&layout(&stacked_targets(clients, focused, targets, workspace, tiles).1)) {
+            fitting = tiles;
+        } else {
+            too_many = tiles;
+        }
+    }
+
+    let (shown, tile_targets) = stacked_targets(clients, focused, targets, workspace, fitting);
+    let tiles = layout(&tile_targets);
+    let stack_tile = tiles[fitting - 1];
+    STACKS.with(|s| {
+        let windows = clients[fitting - 1..].iter().map(|c| c.id()).collect();
+        s.borrow_mut().insert(workspace, Stack { windows, shown, fresh: true });
+    });
+
+    clients[..fitting - 1]
+        .iter()
+        .zip(tiles)
+        .map(|(c, r)| (c.id(), Some(r)))
+        .chain(
+            clients[fitting - 1..]
+                .iter()
+                .map(|c| (c.id(), Some(stack_tile).filter(|_| c.id() == shown))),
+        )
+        .collect()
+}
+
+/// Works out which window the stack shows if there are only enough tiles for the first
+/// `tile_count - 1` windows and the stack, along with the targets for those tiles.
+fn stacked_targets(
+    clients: &[&Client],
+    focused: Option<WinId>,
+    targets: &[Target],
+    workspace: usize,
+    tile_count: usize,
+) -> (WinId, Vec<Target>) {
+    let stack_start = tile_count - 1;
+    let in_stack = |id: WinId| clients[stack_start..].iter().position(|c| c.id() == id);
+
+    let previous = STACKS.with(|s| s.borrow().get(&workspace).map(|s| s.shown));
+    let shown = focused.and_then(in_stack).or_else(|| previous.and_then(in_stack)).unwrap_or(0)
+        + stack_start;
+
+    let mut tile_targets = targets[..stack_start].to_vec();
+    tile_targets.push(targets[shown]);
+    (clients[shown].id(), tile_targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forgets_stacks_once_another_layout_runs() {
+        let clients: Vec<Client> = (0..3)
+            .map(|i| Client::new(i, "hi".to_string(), "there".to_string(), 7, false))
+            .collect();
+        let refs: Vec<&Client> = clients.iter().collect();
+        let targets = [Target { aspect_ratio: 1.0, min_size: (0, 0) }; 3];
+        let one_tile = |targets: &[Target]| vec![Region::new(0, 0, 100, 100); targets.len()];
+
+        layout_with_overflow(&refs, None, &targets, (100, 100), |t| {
+            if t.len() > 1 {
+                vec![Region::new(0, 0, 10, 10); t.len()]
+            } else {
+                one_tile(t)
+            }
+        });
+        settle(&refs);
+        assert_eq!(stacked(7), vec![0, 1, 2]);
+
+        // Laying out some of the windows some other way only forgets those.
+        settle(&refs[2..]);
+        assert_eq!(stacked(7), vec![0, 1]);
+        settle(&refs);
+        assert!(stacked(7).is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+//! Picks the layout each screen starts out with from its shape, so that eg a portrait monitor
+//! starts with a layout that stacks windows rather than squeezing them into slivers.
+//!
+//! The default is applied to the workspaces on each screen when the screens are detected (or
+//! change), and to a workspace whenever it is moved onto a screen.  Once a workspace's layout has
+//! been changed by hand (eg with `cycle_layout`) it is left alone until the screens change.
+//!
+
+use std::collections::HashSet;
+
+use penrose::{core::ring::Selector, data_types::Region, hooks::Hook, WindowManager};
+
+use super::utils;
+
+/// ScreenDefaults is the hook that sets the layouts.
+pub struct ScreenDefaults {
+    portrait: String,
+    landscape: String,
+    ultrawide: String,
+    ultrawide_aspect_ratio: f32,
+    // Workspaces whose layouts have been picked by hand.
+    overridden: HashSet<usize>,
+}
+
+/// All of the settings afforded by ScreenDefaults.  Each layout is given by its symbol, which
+/// should be the symbol of one of the layouts in the config.
+#[derive(Clone, Debug)]
+pub struct Configuration<'a> {
+    /// The layout for screens taller than they are wide.
+    pub portrait: &'a str,
+    /// The layout for ordinary screens.
+    pub landscape: &'a str,
+    /// The layout for very wide screens.
+    pub ultrawide: &'a str,
+    /// Screens at least this wide (relative to their height) count as ultrawide.
+    pub ultrawide_aspect_ratio: f32,
+}
+
+impl ScreenDefaults {
+    /// Creates the hook.
+    pub fn new(config: &Configuration) -> ScreenDefaults {
+        ScreenDefaults {
+            portrait: config.portrait.to_string(),
+            landscape: config.landscape.to_string(),
+            ultrawide: config.ultrawide.to_string(),
+            ultrawide_aspect_ratio: config.ultrawide_aspect_ratio,
+            overridden: HashSet::new(),
+        }
+    }
+
+    fn symbol_for(&self, screen: &Region) -> &str {
+        let aspect_ratio = utils::aspect_ratio(screen);
+        if aspect_ratio < 1.0 {
+            &self.portrait
+        } else if aspect_ratio >= self.ultrawide_aspect_ratio {
+            &self.ultrawide
+        } else {
+            &self.landscape
+        }
+    }
+
+    /// Sets the default layout for the workspace on the given screen, unless it was picked by hand.
+    fn apply(&mut self, wm: &mut WindowManager, screen_index: usize) {
+        let (workspace_index, region) = match wm.screen(&Selector::Index(screen_index)) {
+            Some(screen) => (screen.wix, screen.region(false)),
+            None => return,
+        };
+        if self.overridden.contains(&workspace_index) {
+            return;
+        }
+
+        let symbol = self.symbol_for(&region).to_string();
+        if let Some(workspace) = wm.workspace_mut(&Selector::Index(workspace_index)) {
+            if workspace.layout_symbol() != symbol && workspace.try_set_layout(&symbol).is_some() {
+                wm.layout_screen(screen_index);
+            }
+        }
+    }
+
+    fn apply_to_all(&mut self, wm: &mut WindowManager) {
+        self.overridden.clear();
+        for screen_index in 0..wm.n_screens() {
+            self.apply(wm, screen_index);
+        }
+    }
+}
+
+impl Hook for ScreenDefaults {
+    fn layout_change(&mut self, _: &mut WindowManager<'_>, workspace_index: usize, _: usize) {
+        self.overridden.insert(workspace_index);
+    }
+
+    fn workspace_change(&mut self, wm: &mut WindowManager<'_>, _: usize, _: usize) {
+        let screen_index = wm.active_screen_index();
+        self.apply(wm, screen_index);
+    }
+
+    fn screens_updated(&mut self, wm: &mut WindowManager<'_>, _: &[Region]) {
+        self.apply_to_all(wm);
+    }
+
+    fn startup(&mut self, wm: &mut WindowManager<'_>) {
+        self.apply_to_all(wm);
+    }
+}
+
+impl<'a> Default for Configuration<'a> {
+    fn default() -> Configuration<'a> {
+        Configuration {
+            portrait: "[vfocus]",
+            landscape: "[focus]",
+            ultrawide: "[scroll]",
+            // Between 16:9 (1.78) and 21:9 (2.33).
+            ultrawide_aspect_ratio: 2.2,
+        }
+    }
+}
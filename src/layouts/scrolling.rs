@@ -0,0 +1,144 @@
+//! The scrolling layout (a la PaperWM) gives every window a full height column as close to its
+//! target aspect ratio as the screen allows, no matter how many windows there are.  The columns
+//! form a strip that runs off the edge of the screen, and the screen scrolls along the strip (by
+//! whole columns, as little as it can) to keep the focused window in view.
+//!
+//! Penrose regions can't be above or left of the screen, so the columns out of view (even partly)
+//! are parked beyond the right hand edge of the screen instead, in strip order.
+//!
+
+use std::{cell::RefCell, collections::HashMap};
+
+use penrose::{
+    client::Client,
+    data_types::{Region, ResizeAction, WinId},
+    layout::LayoutFunc,
+};
+
+use super::{utils, LayoutSettings, Target};
+
+thread_local! {
+    // The first window in view on each workspace.  Layouts are plain functions, so like the main
+    // region this has to live outside of them.
+    static FIRST_VISIBLE: RefCell<HashMap<usize, WinId>> = RefCell::new(HashMap::new());
+}
+
+/// Creates and returns a closure that performs scrolling layouts.
+pub fn new<S: LayoutSettings>() -> LayoutFunc {
+    |a, b, c, _, _| {
+        S::fit_windows(a, do_scrolling_layout(a, b, c, &S::targets_for(a), S::gaps(a.len())))
+    }
+}
+
+fn do_scrolling_layout(
+    clients: &[&Client],
+    focused: Option<WinId>,
+    to_fill: &Region,
+    targets: &[Target],
+    (gap, outer_gap): (u32, u32),
+) -> Vec<ResizeAction> {
+    let workspace = match clients.first() {
+        Some(c) => c.workspace(),
+        None => return Vec::new(),
+    };
+    let (screen_x, _, screen_w, _) = to_fill.values();
+    let to_fill = &utils::shrink(to_fill, outer_gap);
+    let widths = column_widths(to_fill, targets);
+
+    let ids: Vec<WinId> = clients.iter().map(|c| c.id()).collect();
+    let focused = focused.and_then(|id| ids.iter().position(|i| *i == id));
+    let first = FIRST_VISIBLE.with(|f| {
+        let mut f = f.borrow_mut();
+        let previous = f.get(&workspace).and_then(|id| ids.iter().position(|i| i == id));
+        let first = scroll(&widths, to_fill, gap, previous.unwrap_or(0), focused);
+        f.insert(workspace, ids[first]);
+        first
+    });
+
+    let (x, y, w, h) = to_fill.values();
+    let (mut column_x, mut parked_x) = (x, screen_x + screen_w + gap);
+    let mut past_the_edge = false;
+    let mut place = |i: usize| {
+        // Once a column doesn't fit, the ones after it are out of view too (even narrower ones).
+        past_the_edge = past_the_edge || (i >= first && column_x + widths[i] > x + w);
+        let next_x = if i < first || past_the_edge { &mut parked_x } else { &mut column_x };
+        let r = Region::new(*next_x, y, widths[i], h);
+        *next_x += widths[i] + gap;
+        Some(r)
+    };
+    clients.iter().enumerate().map(|(i, c)| (c.id(), place(i))).collect()
+}
+
+/// Each window gets the full height of the region, and as much of the width as its target aspect
+/// ratio calls for.
+fn column_widths(to_fill: &Region, targets: &[Target]) -> Vec<u32> {
+    let (_, _, w, h) = to_fill.values();
+    targets.iter().map(|t| ((h as f32 * t.aspect_ratio) as u32).min(w).max(1)).collect()
+}
+
+/// Works out the first column to show, moving along the strip from the `previous` first column
+/// only as far as it takes to bring the focused column completely into view.
+fn scroll(
+    widths: &[u32],
+    to_fill: &Region,
+    gap: u32,
+    previous: usize,
+    focused: Option<usize>,
+) -> usize {
+    let focused = match focused {
+        Some(i) => i,
+        None => return previous,
+    };
+    if focused < previous {
+        return focused;
+    }
+
+    let (_, _, w, _) = to_fill.values();
+    let mut first = previous;
+    while first < focused
+        && widths[first..=focused].iter().sum::<u32>() + gap * (focused - first) as u32 > w
+    {
+        first += 1;
+    }
+    first
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parks_columns_out_of_view_past_the_edge() {
+        let clients: Vec<Client> = (0..3)
+            .map(|i| Client::new(i, "hi".to_string(), "there".to_string(), 1, false))
+            .collect();
+        let clients: Vec<&Client> = clients.iter().collect();
+        let targets: Vec<Target> = [1.0, 1.5, 0.4]
+            .iter()
+            .map(|&aspect_ratio| Target { aspect_ratio, min_size: (0, 0) })
+            .collect();
+        let screen = Region::new(0, 0, 2000, 1000);
+
+        // The second column doesn't fit, so the narrower third one can't take its place.
+        let actions = do_scrolling_layout(&clients, None, &screen, &targets, (0, 0));
+        assert_eq!(
+            actions,
+            vec![
+                (0, Some(Region::new(0, 0, 1000, 1000))),
+                (1, Some(Region::new(2000, 0, 1500, 1000))),
+                (2, Some(Region::new(3500, 0, 400, 1000))),
+            ]
+        );
+
+        // Focusing the third column scrolls the first one out of view.
+        let actions = do_scrolling_layout(&clients, Some(2), &screen, &targets, (0, 0));
+        assert_eq!(
+            actions,
+            vec![
+                (0, Some(Region::new(2000, 0, 1000, 1000))),
+                (1, Some(Region::new(0, 0, 1500, 1000))),
+                (2, Some(Region::new(1500, 0, 400, 1000))),
+            ]
+        );
+    }
+}
@@ -0,0 +1,209 @@
+//! Reads the size hints (WM_NORMAL_HINTS) windows set for themselves, so the layouts can honor
+//! them (see `SizeHints`).
+//!
+//! Penrose doesn't read these hints, so `SizeHintsReader` is a hook that asks the X server for
+//! each window's hints as it is created, and remembers them until the window goes away.  Windows
+//! can change their hints later (eg a terminal whose font changes), so the hook also watches for
+//! that on its own connection, and lays the screen out again with the new hints.  It only checks
+//! after penrose handles an event, but clients that change their hints usually ask to be resized
+//! too.  Windows whose hints are missing or wrong can be given hints by class instead (see
+//! `LayoutSettings::CLASS_SIZE_HINTS`).
+//!
+
+use std::{cell::RefCell, collections::HashMap};
+
+use penrose::{
+    client::Client, core::ring::Selector, data_types::WinId, hooks::Hook, WindowManager,
+};
+
+use super::SizeHints;
+
+thread_local! {
+    // The hook is owned by penrose, out of the layouts' reach, so it leaves the hints here for
+    // them to find (see `hints_for`).
+    static HINTS: RefCell<HashMap<WinId, SizeHints>> = RefCell::new(HashMap::new());
+}
+
+// The flags saying which of the WM_SIZE_HINTS fields are set (see the ICCCM, section 4.1.2.3).
+const P_MIN_SIZE: u32 = 1 << 4;
+const P_MAX_SIZE: u32 = 1 << 5;
+const P_RESIZE_INC: u32 = 1 << 6;
+const P_ASPECT: u32 = 1 << 7;
+const P_BASE_SIZE: u32 = 1 << 8;
+
+/// WM_SIZE_HINTS is 18 32 bit values long.
+const WM_SIZE_HINTS_LENGTH: u32 = 18;
+/// Older clients only set the first 15, without the base size (or gravity).
+const OLD_WM_SIZE_HINTS_LENGTH: usize = 15;
+
+/// SizeHintsReader is the hook that reads the windows' size hints.
+pub struct SizeHintsReader {
+    conn: xcb::Connection,
+    /// The windows whose properties the hook is watching.
+    watched: Vec<WinId>,
+}
+
+impl SizeHintsReader {
+    /// Creates the hook, with a connection of its own to the X server.
+    pub fn new() -> Result<SizeHintsReader, xcb::ConnError> {
+        let (conn, _) = xcb::Connection::connect(None)?;
+        Ok(SizeHintsReader { conn, watched: Vec::new() })
+    }
+
+    /// Reads the window's hints afresh, and returns whether they changed.
+    fn update(&self, id: WinId) -> bool {
+        let hints = self.read(id);
+        HINTS.with(|h| {
+            let mut h = h.borrow_mut();
+            let old = match hints {
+                Some(hints) => h.insert(id, hints),
+                None => h.remove(&id),
+            };
+            old != hints
+        })
+    }
+
+    fn read(&self, id: WinId) -> Option<SizeHints> {
+        let reply = xcb::get_property(
+            &self.conn,
+            false,
+            id,
+            xcb::ATOM_WM_NORMAL_HINTS,
+            xcb::ATOM_WM_SIZE_HINTS,
+            0,
+            WM_SIZE_HINTS_LENGTH,
+        )
+        .get_reply()
+        .ok()?;
+        if reply.format() != 32 {
+            return None;
+        }
+        parse(reply.value::<u32>())
+    }
+}
+
+impl Hook for SizeHintsReader {
+    fn new_client(&mut self, _: &mut WindowManager<'_>, c: &mut Client) {
+        // Watch for changes before the first read, so none slip through in between.
+        let mask = [(xcb::CW_EVENT_MASK, xcb::EVENT_MASK_PROPERTY_CHANGE)];
+        xcb::change_window_attributes(&self.conn, c.id(), &mask);
+        self.conn.flush();
+        self.watched.push(c.id());
+        self.update(c.id());
+    }
+
+    fn remove_client(&mut self, _: &mut WindowManager<'_>, id: WinId) {
+        self.watched.retain(|w| *w != id);
+        HINTS.with(|h| h.borrow_mut().remove(&id));
+    }
+
+    fn event_handled(&mut self, wm: &mut WindowManager<'_>) {
+        // The events say which property of which window changed, but getting at that takes unsafe
+        // code, so every window's hints are read again instead.  Titles change far more often than
+        // hints do, but there are never very many windows.
+        let mut changed = false;
+        while self.conn.poll_for_event().is_some() {
+            changed = true;
+        }
+        if !changed {
+            return;
+        }
+
+        let mut changed_workspaces = Vec::new();
+        for id in &self.watched {
+            if self.update(*id) {
+                if let Some(client) = wm.client(&Selector::WinId(*id)) {
+                    changed_workspaces.push(client.workspace());
+                }
+            }
+        }
+        for screen_index in 0..wm.n_screens() {
+            let workspace_index = match wm.screen(&Selector::Index(screen_index)) {
+                Some(screen) => screen.wix,
+                None => continue,
+            };
+            if changed_workspaces.contains(&workspace_index) {
+                wm.layout_screen(screen_index);
+            }
+        }
+    }
+}
+
+/// The size hints the given window asked for, if it asked for any.
+pub fn hints_for(id: WinId) -> Option<SizeHints> {
+    HINTS.with(|h| h.borrow().get(&id).copied())
+}
+
+/// Makes sense of the values of a WM_SIZE_HINTS property.  Sizes that are missing are filled in
+/// the way the ICCCM says to: the base size stands in for the min size and vice versa.
+pub fn parse(values: &[u32]) -> Option<SizeHints> {
+    if values.len() < OLD_WM_SIZE_HINTS_LENGTH {
+        return None;
+    }
+    let mut all = [0; WM_SIZE_HINTS_LENGTH as usize];
+    let length = values.len().min(all.len());
+    all[..length].copy_from_slice(&values[..length]);
+    let values = &all;
+
+    let flags = values[0];
+    let is_set = |flag: u32| flags & flag != 0;
+    let min = (values[5], values[6]);
+    let base = (values[15], values[16]);
+
+    let mut hints = SizeHints::NONE;
+    if is_set(P_MIN_SIZE) {
+        hints.min_size = min;
+    } else if is_set(P_BASE_SIZE) {
+        hints.min_size = base;
+    }
+    if is_set(P_BASE_SIZE) {
+        hints.base_size = base;
+    } else if is_set(P_MIN_SIZE) {
+        hints.base_size = min;
+    }
+    if is_set(P_MAX_SIZE) && values[7] > 0 && values[8] > 0 {
+        hints.max_size = Some((values[7], values[8]));
+    }
+    if is_set(P_RESIZE_INC) && values[9] > 0 && values[10] > 0 {
+        hints.resize_increments = (values[9], values[10]);
+    }
+    if is_set(P_ASPECT) && values[12] > 0 && values[14] > 0 {
+        let min_aspect = values[11] as f32 / values[12] as f32;
+        let max_aspect = values[13] as f32 / values[14] as f32;
+        if min_aspect > 0.0 && min_aspect <= max_aspect {
+            hints.aspect_range = Some((min_aspect, max_aspect));
+        }
+    }
+    Some(hints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_wm_size_hints() {
+        // A terminal: 80x24 cells of 9x19 pixels, plus 2 pixels of padding.
+        let mut values = [0; 18];
+        values[0] = P_MIN_SIZE | P_RESIZE_INC | P_BASE_SIZE;
+        values[5..7].copy_from_slice(&[20, 40]);
+        values[9..11].copy_from_slice(&[9, 19]);
+        values[15..17].copy_from_slice(&[2, 2]);
+        let hints = parse(&values).unwrap();
+        assert_eq!(hints.min_size, (20, 40));
+        assert_eq!(hints.base_size, (2, 2));
+        assert_eq!(hints.resize_increments, (9, 19));
+        assert_eq!(hints.max_size, None);
+
+        // A video player that wants to be 16:9, and only gave a base size.
+        let mut values = [0; 18];
+        values[0] = P_ASPECT | P_BASE_SIZE;
+        values[11..15].copy_from_slice(&[16, 9, 16, 9]);
+        values[15..17].copy_from_slice(&[160, 90]);
+        let hints = parse(&values).unwrap();
+        assert_eq!(hints.min_size, (160, 90));
+        assert_eq!(hints.aspect_range, Some((16.0 / 9.0, 16.0 / 9.0)));
+
+        assert_eq!(parse(&[0; 4]), None);
+    }
+}
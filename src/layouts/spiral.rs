@@ -0,0 +1,151 @@
+//! The spiral layout gives the first window a share of the screen, the second window the same
+//! share of what is left, and so on, spiraling in towards the last window.
+//!
+//! Rather than blindly alternating between cutting off columns and rows, each cut goes whichever
+//! way keeps both the window and the space left over closest to their target aspect ratios.  Once
+//! the tiles would be too small to be useful (see `utils::MIN_SPLIT_PX`), the rest of the windows
+//! share the last tile (see the overflow module).
+//!
+
+use penrose::{
+    client::Client,
+    data_types::{Region, ResizeAction, WinId},
+    layout::LayoutFunc,
+};
+
+use super::{overflow, utils, validation, LayoutSettings, Target};
+
+/// Creates and returns a closure that performs spiral layouts.
+pub fn new<S: LayoutSettings>() -> LayoutFunc {
+    |a, b, c, _, e| S::fit_windows(a, do_spiral_layout::<S>(a, b, c, e))
+}
+
+fn do_spiral_layout<S: LayoutSettings>(
+    clients: &[&Client],
+    focused: Option<WinId>,
+    to_fill: &Region,
+    ratio: f32,
+) -> Vec<ResizeAction> {
+    let targets = &S::targets_for(clients);
+    let (gap, outer_gap) = S::gaps(clients.len());
+    let to_fill = &utils::shrink(to_fill, outer_gap);
+    let min_size = (utils::MIN_SPLIT_PX, utils::MIN_SPLIT_PX);
+    let actions = overflow::layout_with_overflow(clients, focused, targets, min_size, |targets| {
+        layout_region_in_spiral(to_fill, ratio, targets, gap)
+    });
+    validation::debug_checked(clients, to_fill, gap, actions)
+}
+
+/// Divides the region into one region per target, `gap` pixels apart, with each taking `ratio`
+/// of the space left over by the ones before it.  Cuts that leave both parts at least
+/// `utils::MIN_SPLIT_PX` are preferred, but with enough targets the regions end up smaller.
+pub fn layout_region_in_spiral(
+    to_fill: &Region,
+    ratio: f32,
+    targets: &[Target],
+    gap: u32,
+) -> Vec<Region> {
+    let mut results = Vec::with_capacity(targets.len());
+    let mut remaining = *to_fill;
+    // Which side of each kind of cut the next window goes on.  Swapping sides after every cut is
+    // what makes it a spiral.
+    let (mut window_on_left, mut window_on_top) = (true, true);
+
+    for (i, target) in targets.iter().enumerate() {
+        let rest_target = match targets.get(i + 1) {
+            Some(t) => t.aspect_ratio,
+            None => {
+                results.push(remaining);
+                break;
+            }
+        };
+
+        let (_, _, w, h) = remaining.values();
+        let window_w = (w.saturating_sub(gap) as f32 * ratio) as u32;
+        let (column, column_rest) = if window_on_left {
+            utils::split_at_width(&remaining, window_w, gap)
+        } else {
+            let (rest, window) =
+                utils::split_at_width(&remaining, w.saturating_sub(gap + window_w), gap);
+            (window, rest)
+        };
+        let window_h = (h.saturating_sub(gap) as f32 * ratio) as u32;
+        let (row, row_rest) = if window_on_top {
+            utils::split_at_height(&remaining, window_h, gap)
+        } else {
+            let (rest, window) =
+                utils::split_at_height(&remaining, h.saturating_sub(gap + window_h), gap);
+            (window, rest)
+        };
+
+        let error = |window: &Region, rest: &Region| {
+            (utils::aspect_ratio(window) - target.aspect_ratio).powi(2)
+                + (utils::aspect_ratio(rest) - rest_target).powi(2)
+        };
+        let fits = |window: &Region, rest: &Region| {
+            utils::big_enough_to_split(window) && utils::big_enough_to_split(rest)
+        };
+        let use_column = match (fits(&column, &column_rest), fits(&row, &row_rest)) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => error(&column, &column_rest) <= error(&row, &row_rest),
+        };
+        if use_column {
+            results.push(column);
+            remaining = column_rest;
+            window_on_left = !window_on_left;
+        } else {
+            results.push(row);
+            remaining = row_rest;
+            window_on_top = !window_on_top;
+        }
+    }
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{validation, Widescreen};
+    use proptest::prelude::*;
+
+    struct Gapped;
+
+    impl LayoutSettings for Gapped {
+        const INNER_GAP_PX: u32 = 11;
+        const OUTER_GAP_PX: u32 = 5;
+    }
+
+    proptest! {
+        #[test]
+        fn tiles_the_screen(
+            w in 640u32..7680,
+            h in 480u32..2160,
+            count in 1usize..40,
+            ratio in 0.2f32..0.8,
+        ) {
+            let clients: Vec<Client> = (0..count)
+                .map(|i| Client::new(i as u32, "hi".to_string(), "there".to_string(), 1, false))
+                .collect();
+            let refs: Vec<&Client> = clients.iter().collect();
+            let screen = Region::new(0, 0, w, h);
+
+            // The windows that don't fit are stacked, and all but one of those are hidden.
+            let layouts = [
+                (do_spiral_layout::<Widescreen>(&refs, None, &screen, ratio), screen, 0),
+                (
+                    do_spiral_layout::<Gapped>(&refs, None, &screen, ratio),
+                    utils::shrink(&screen, Gapped::OUTER_GAP_PX),
+                    Gapped::INNER_GAP_PX,
+                ),
+            ];
+            for (layout, inner, gap) in layouts.iter() {
+                prop_assert_eq!(layout.len(), count);
+                let shown: Vec<Region> = layout.iter().filter_map(|(_, r)| *r).collect();
+                let n = shown.len();
+                prop_assert_eq!(validation::validate(inner, &shown, n, *gap), Ok(()));
+            }
+        }
+    }
+}
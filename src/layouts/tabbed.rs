@@ -0,0 +1,34 @@
+//! The tabbed layout shows one window at a time, the focused one, filling the screen below a thin
+//! strip.  The strip is left empty for the tab strip widget (see widgets::tab_strip) to list the
+//! workspace's windows in, like the tabs of a browser.
+//!
+
+use penrose::{
+    client::Client,
+    data_types::{Region, ResizeAction, WinId},
+    layout::LayoutFunc,
+};
+
+use super::{utils, LayoutSettings, TAB_STRIP_HEIGHT_PX};
+
+/// Creates and returns a closure that performs tabbed layouts.
+pub fn new<S: LayoutSettings>() -> LayoutFunc {
+    |a, b, c, _, _| S::fit_windows(a, do_tabbed_layout(a, b, c, S::gaps(a.len())))
+}
+
+fn do_tabbed_layout(
+    clients: &[&Client],
+    focused: Option<WinId>,
+    to_fill: &Region,
+    (_, outer_gap): (u32, u32),
+) -> Vec<ResizeAction> {
+    let (_, below_strip) = utils::split_at_height(to_fill, TAB_STRIP_HEIGHT_PX, 0);
+    let window = utils::shrink(&below_strip, outer_gap);
+
+    let shown = match focused.filter(|id| clients.iter().any(|c| c.id() == *id)) {
+        Some(id) => Some(id),
+        None => clients.first().map(|c| c.id()),
+    };
+    // The windows without focus are unmapped, rather than hidden behind the focused one.
+    clients.iter().map(|c| (c.id(), Some(window).filter(|_| Some(c.id()) == shown))).collect()
+}
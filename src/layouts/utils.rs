@@ -1,9 +1,22 @@
 //! General purpose utilities to help with building layouts.  Many of these should migrate
 //! upstream.
 
+use std::cmp::Ordering;
+
 use penrose::data_types::Region;
 
-use super::TARGET_ASPECT_RATIO;
+use super::{cost::Cost, SplitDirection, Target};
+
+/// The narrowest (or shortest) the layouts that cut regions in two (eg spiral and bsp) will cut a
+/// region down to.  Windows any smaller are of little use, and cutting them further soon leaves
+/// regions with no area at all.
+pub const MIN_SPLIT_PX: u32 = 32;
+
+/// Is the region at least `MIN_SPLIT_PX` in both directions?
+pub fn big_enough_to_split(r: &Region) -> bool {
+    let (_, _, w, h) = r.values();
+    w >= MIN_SPLIT_PX && h >= MIN_SPLIT_PX
+}
 
 /// Computes the aspect ratio of a given region.
 pub fn aspect_ratio(r: &Region) -> f32 {
@@ -11,43 +24,290 @@ pub fn aspect_ratio(r: &Region) -> f32 {
     w as f32 / h as f32
 }
 
-/// Computes the sum of the square errors of each window's aspect ratio versus the target ratio
-/// of 16:9.
-pub fn aspect_ratio_sse(layout: &Vec<Region>) -> u32 {
-    // u32 instead of f32 because we need Ord
-    // * 1000.0 here to avoid precision loss
-    (layout.iter().map(|r| (aspect_ratio(r) - TARGET_ASPECT_RATIO).powi(2)).sum::<f32>() * 1000.0)
-        as u32
+/// Finds the largest region, centered within `r`, that has exactly the given aspect ratio (give or
+/// take a pixel of rounding).
+pub fn letterbox(r: &Region, ratio: f32) -> Region {
+    let (x, y, w, h) = r.values();
+    if w == 0 || h == 0 || ratio <= 0.0 {
+        return *r;
+    }
+
+    if aspect_ratio(r) > ratio {
+        let new_w = ((h as f32 * ratio).round() as u32).min(w);
+        Region::new(x + (w - new_w) / 2, y, new_w, h)
+    } else {
+        let new_h = ((w as f32 / ratio).round() as u32).min(h);
+        Region::new(x, y + (h - new_h) / 2, w, new_h)
+    }
+}
+
+/// Reflects `r` left to right within `screen`.
+pub fn mirror(r: &Region, screen: &Region) -> Region {
+    let (sx, _, sw, _) = screen.values();
+    let (x, y, w, h) = r.values();
+    Region::new(sx + (sx + sw).saturating_sub(x + w), y, w, h)
+}
+
+/// Reflects `r` top to bottom within `screen`.
+pub fn flip(r: &Region, screen: &Region) -> Region {
+    let (_, sy, _, sh) = screen.values();
+    let (x, y, w, h) = r.values();
+    Region::new(x, sy + (sy + sh).saturating_sub(y + h), w, h)
+}
+
+/// Reflects `r` across the diagonal running from the top left corner of `screen`.
+pub fn transpose(r: &Region, screen: &Region) -> Region {
+    let (sx, sy, _, _) = screen.values();
+    let (x, y, w, h) = r.values();
+    Region::new(sx + y.saturating_sub(sy), sy + x.saturating_sub(sx), h, w)
+}
+
+/// Counts the windows whose regions are smaller than they can sensibly be drawn at.
+pub fn min_size_violations(layout: &[Region], targets: &[Target]) -> usize {
+    layout
+        .iter()
+        .zip(targets)
+        .filter(|(r, t)| {
+            let (_, _, w, h) = r.values();
+            w < t.min_size.0 || h < t.min_size.1
+        })
+        .count()
+}
+
+/// Scores a layout for the fair search: tilings that squash fewer windows below their minimum
+/// size always win, with the cost breaking ties.
+pub fn fair_score(layout: &[Region], targets: &[Target], cost: &dyn Cost) -> (usize, f32) {
+    (min_size_violations(layout, targets), cost.cost(layout, targets))
+}
+
+/// Reorders the regions of a layout so that the sum of the square errors between each window's
+/// target ratio and its region's aspect ratio is minimized.
+///
+/// Pairing the regions and the targets in sorted order is optimal for square error, and windows
+/// that share a target keep their regions in layout order, so if every window wants the same
+/// shape the layout comes back untouched.
+pub fn assign_regions(layout: Vec<Region>, targets: &[Target]) -> Vec<Region> {
+    let targets: Vec<f32> = targets.iter().map(|t| t.aspect_ratio).collect();
+    if targets.windows(2).all(|w| w[0] == w[1]) {
+        return layout;
+    }
+
+    let mut by_target: Vec<usize> = (0..targets.len()).collect();
+    by_target.sort_by(|&a, &b| targets[a].partial_cmp(&targets[b]).unwrap_or(Ordering::Equal));
+    let mut by_ratio: Vec<usize> = (0..layout.len()).collect();
+    by_ratio.sort_by(|&a, &b| {
+        aspect_ratio(&layout[a]).partial_cmp(&aspect_ratio(&layout[b])).unwrap_or(Ordering::Equal)
+    });
+
+    let mut results = layout.clone();
+    let mut start = 0;
+    while start < by_target.len() {
+        // Each run of equal targets gets its regions handed out in layout order.
+        let target = targets[by_target[start]];
+        let end = start + by_target[start..].iter().take_while(|&&i| targets[i] == target).count();
+
+        let mut windows = by_target[start..end].to_vec();
+        let mut regions = by_ratio[start..end].to_vec();
+        windows.sort_unstable();
+        regions.sort_unstable();
+        for (w, r) in windows.into_iter().zip(regions) {
+            results[w] = layout[r];
+        }
+
+        start = end;
+    }
+
+    results
+}
+
+/// Shrinks the region by `gap` pixels on every side.
+pub fn shrink(r: &Region, gap: u32) -> Region {
+    let (x, y, w, h) = r.values();
+    let gap = gap.min(w / 2).min(h / 2);
+    Region::new(x + gap, y + gap, w - 2 * gap, h - 2 * gap)
+}
+
+/// Splits this region in two, side by side or one above the other, giving `ratio` of the space to
+/// the first part and leaving `gap` pixels between them.
+pub fn split(r: &Region, direction: SplitDirection, ratio: f32, gap: u32) -> (Region, Region) {
+    let (_, _, w, h) = r.values();
+    match direction {
+        SplitDirection::LeftRight => {
+            split_at_width(r, (w.saturating_sub(gap) as f32 * ratio) as u32, gap)
+        }
+        SplitDirection::TopBottom => {
+            split_at_height(r, (h.saturating_sub(gap) as f32 * ratio) as u32, gap)
+        }
+    }
+}
+
+/// Works out which way to split this region in half so that both halves come out closest to their
+/// (first, second) target aspect ratios.  Ties go side by side.
+pub fn best_split_direction(r: &Region, gap: u32, (first, second): (f32, f32)) -> SplitDirection {
+    let cost = |direction| {
+        let (a, b) = split(r, direction, 0.5, gap);
+        (aspect_ratio(&a) - first).powi(2) + (aspect_ratio(&b) - second).powi(2)
+    };
+    if cost(SplitDirection::TopBottom) < cost(SplitDirection::LeftRight) {
+        SplitDirection::TopBottom
+    } else {
+        SplitDirection::LeftRight
+    }
+}
+
+/// Splits this region in two, the first part `new_width` wide, with `gap` pixels between them.
+pub fn split_at_width(r: &Region, new_width: u32, gap: u32) -> (Region, Region) {
+    let (x, y, w, h) = r.values();
+    let new_width = new_width.min(w);
+    let gap = gap.min(w - new_width);
+    (Region::new(x, y, new_width, h), Region::new(x + new_width + gap, y, w - new_width - gap, h))
 }
 
-/// Divides this region into `count` equal columns.
-pub fn split_into_columns(r: &Region, count: u32) -> Vec<Region> {
+/// Splits this region in two, the first part `new_height` tall, with `gap` pixels between them.
+pub fn split_at_height(r: &Region, new_height: u32, gap: u32) -> (Region, Region) {
+    let (x, y, w, h) = r.values();
+    let new_height = new_height.min(h);
+    let gap = gap.min(h - new_height);
+    (
+        Region::new(x, y, w, new_height),
+        Region::new(x, y + new_height + gap, w, h - new_height - gap),
+    )
+}
+
+/// Divides this region into `count` equal columns, with `gap` pixels between each of them.
+pub fn split_into_columns(r: &Region, count: u32, gap: u32) -> Vec<Region> {
+    let mut results = Vec::with_capacity(count as usize);
+    push_columns(&mut results, r, count, gap);
+    results
+}
+
+/// Like `split_into_columns`, but adds the columns to `results` rather than a new Vec.
+pub fn push_columns(results: &mut Vec<Region>, r: &Region, count: u32, gap: u32) {
     assert!(count > 0, "Cannot split into 0 columns.");
     let (x, y, w, h) = r.values();
-    let width = w / count;
+    let width = w.saturating_sub(gap * (count - 1)) / count;
 
-    let mut results = Vec::new();
     for i in 0..(count - 1) {
-        results.push(Region::new(x + i * width, y, width, h));
+        results.push(Region::new(x + i * (width + gap), y, width, h));
     }
-    let accounted_for = (count - 1) * width;
+    let accounted_for = ((count - 1) * (width + gap)).min(w);
     results.push(Region::new(x + accounted_for, y, w - accounted_for, h));
+}
 
+/// Divides this region into `count` equal rows, with `gap` pixels between each of them.
+pub fn split_into_rows(r: &Region, count: u32, gap: u32) -> Vec<Region> {
+    let mut results = Vec::with_capacity(count as usize);
+    push_rows(&mut results, r, count, gap);
     results
 }
 
-/// Divides this region into `count` equal rows.
-pub fn split_into_rows(r: &Region, count: u32) -> Vec<Region> {
+/// Like `split_into_rows`, but adds the rows to `results` rather than a new Vec.
+pub fn push_rows(results: &mut Vec<Region>, r: &Region, count: u32, gap: u32) {
     assert!(count > 0, "Cannot split into 0 rows.");
     let (x, y, w, h) = r.values();
-    let height = h / count;
+    let height = h.saturating_sub(gap * (count - 1)) / count;
 
-    let mut results = Vec::new();
     for i in 0..(count - 1) {
-        results.push(Region::new(x, y + i * height, w, height));
+        results.push(Region::new(x, y + i * (height + gap), w, height));
     }
-    let accounted_for = (count - 1) * height;
+    let accounted_for = ((count - 1) * (height + gap)).min(h);
     results.push(Region::new(x, y + accounted_for, w, h - accounted_for));
+}
 
-    results
+/// Divides this region into columns with widths in proportion to the given weights, with `gap`
+/// pixels between each of them.
+pub fn split_into_weighted_columns(r: &Region, weights: &[f32], gap: u32) -> Vec<Region> {
+    assert!(!weights.is_empty(), "Cannot split into 0 columns.");
+    let (x, y, w, h) = r.values();
+
+    let mut offset = 0;
+    weighted_lengths(w, weights, gap)
+        .into_iter()
+        .map(|width| {
+            let column = Region::new(x + offset.min(w), y, width, h);
+            offset += width + gap;
+            column
+        })
+        .collect()
+}
+
+/// Divides this region into rows with heights in proportion to the given weights, with `gap`
+/// pixels between each of them.
+pub fn split_into_weighted_rows(r: &Region, weights: &[f32], gap: u32) -> Vec<Region> {
+    assert!(!weights.is_empty(), "Cannot split into 0 rows.");
+    let (x, y, w, h) = r.values();
+
+    let mut offset = 0;
+    weighted_lengths(h, weights, gap)
+        .into_iter()
+        .map(|height| {
+            let row = Region::new(x, y + offset.min(h), w, height);
+            offset += height + gap;
+            row
+        })
+        .collect()
+}
+
+fn weighted_lengths(length: u32, weights: &[f32], gap: u32) -> Vec<u32> {
+    let available = length.saturating_sub(gap * (weights.len() as u32 - 1));
+    let total: f32 = weights.iter().sum();
+    let mut lengths: Vec<u32> =
+        weights.iter().map(|weight| (available as f32 * weight / total) as u32).collect();
+
+    // Like the even splits, the last part gets whatever rounding down left over.
+    let used: u32 = lengths.iter().sum();
+    if let Some(last) = lengths.last_mut() {
+        *last += available.saturating_sub(used);
+    }
+    lengths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::validation;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn even_splits_tile_the_region(
+            (x, y, w, h) in (0u32..4000, 0u32..2000, 640u32..7680, 480u32..2160),
+            count in 1u32..20,
+            gap in 0u32..12,
+        ) {
+            let r = Region::new(x, y, w, h);
+            let n = count as usize;
+            let columns = split_into_columns(&r, count, gap);
+            prop_assert_eq!(validation::validate(&r, &columns, n, gap), Ok(()));
+            let rows = split_into_rows(&r, count, gap);
+            prop_assert_eq!(validation::validate(&r, &rows, n, gap), Ok(()));
+        }
+
+        #[test]
+        fn weighted_splits_tile_the_region(
+            (x, y, w, h) in (0u32..4000, 0u32..2000, 640u32..7680, 480u32..2160),
+            weights in prop::collection::vec(0.1f32..1.0, 1..8),
+            gap in 0u32..12,
+        ) {
+            let r = Region::new(x, y, w, h);
+            let n = weights.len();
+            let columns = split_into_weighted_columns(&r, &weights, gap);
+            prop_assert_eq!(validation::validate(&r, &columns, n, gap), Ok(()));
+            let rows = split_into_weighted_rows(&r, &weights, gap);
+            prop_assert_eq!(validation::validate(&r, &rows, n, gap), Ok(()));
+        }
+
+        #[test]
+        fn halving_splits_tile_the_region(
+            (x, y, w, h) in (0u32..4000, 0u32..2000, 640u32..7680, 480u32..2160),
+            ratio in 0.05f32..0.95,
+            gap in 0u32..12,
+        ) {
+            let r = Region::new(x, y, w, h);
+            for direction in [SplitDirection::LeftRight, SplitDirection::TopBottom].iter() {
+                let (a, b) = split(&r, *direction, ratio, gap);
+                prop_assert_eq!(validation::validate(&r, &[a, b], 2, gap), Ok(()));
+            }
+        }
+    }
 }
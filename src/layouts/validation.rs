@@ -0,0 +1,197 @@
+//! Checks that a layout really does tile the space it was given: one region per window, none of
+//! them empty, none of them overlapping or spilling out of the space, and no space left over
+//! (beyond the gaps).
+//!
+//! In debug builds every tiling layout checks its own work and logs it if it got it wrong (the
+//! tests check the layouts properly).  The tabbed and scrolling layouts aren't tilings (they leave
+//! windows out of view on purpose), so they aren't checked.
+//!
+
+use std::fmt;
+
+use penrose::{
+    client::Client,
+    data_types::{Region, ResizeAction},
+};
+
+/// The ways a layout can go wrong.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayoutError {
+    /// There wasn't one region per window.
+    WrongCount {
+        /// How many regions there should have been.
+        expected: usize,
+        /// How many there were.
+        actual: usize,
+    },
+    /// A region with no area.
+    Empty(Region),
+    /// A region that isn't entirely within the space being tiled.
+    OutOfBounds(Region),
+    /// Two regions that cover some of the same space.
+    Overlapping(Region, Region),
+    /// Some of the space (more than the gaps) isn't covered by any region.
+    Uncovered {
+        /// The area, in pixels, that nothing covers.
+        area: u64,
+    },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::WrongCount { expected, actual } => {
+                write!(f, "expected {} regions but got {}", expected, actual)
+            }
+            LayoutError::Empty(r) => write!(f, "{:?} is empty", r),
+            LayoutError::OutOfBounds(r) => write!(f, "{:?} is out of bounds", r),
+            LayoutError::Overlapping(a, b) => write!(f, "{:?} overlaps {:?}", a, b),
+            LayoutError::Uncovered { area } => write!(f, "{} pixels are not covered", area),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+/// Checks that `layout` is `expected` non-empty regions that tile `to_fill` without overlapping.
+/// Up to `gap` pixels of space may be left uncovered around each region, for the gaps between
+/// windows.
+pub fn validate(
+    to_fill: &Region,
+    layout: &[Region],
+    expected: usize,
+    gap: u32,
+) -> Result<(), LayoutError> {
+    if layout.len() != expected {
+        return Err(LayoutError::WrongCount { expected, actual: layout.len() });
+    }
+    if let Some(r) = layout.iter().find(|r| r.values().2 == 0 || r.values().3 == 0) {
+        return Err(LayoutError::Empty(*r));
+    }
+    if let Some(r) = layout.iter().find(|r| !contains(to_fill, r)) {
+        return Err(LayoutError::OutOfBounds(*r));
+    }
+    for (i, a) in layout.iter().enumerate() {
+        if let Some(b) = layout[i + 1..].iter().find(|b| overlaps(a, b)) {
+            return Err(LayoutError::Overlapping(*a, *b));
+        }
+    }
+
+    let padded: Vec<Region> = layout.iter().map(|r| pad(r, gap, to_fill)).collect();
+    let (_, _, w, h) = to_fill.values();
+    let area = w as u64 * h as u64 - union_area(&padded);
+    if area > 0 && !layout.is_empty() {
+        return Err(LayoutError::Uncovered { area });
+    }
+    Ok(())
+}
+
+/// In debug builds, logs an error unless the windows `actions` shows tile `to_fill` (see
+/// `validate`).  Either way the actions are passed through, since a bad layout beats a dead
+/// window manager (and some spaces, eg 1x1, are too small for any layout to tile).
+pub(super) fn debug_checked(
+    clients: &[&Client],
+    to_fill: &Region,
+    gap: u32,
+    actions: Vec<ResizeAction>,
+) -> Vec<ResizeAction> {
+    if cfg!(debug_assertions) {
+        let shown: Vec<Region> = actions.iter().filter_map(|(_, r)| *r).collect();
+        let result = if actions.len() == clients.len() {
+            validate(to_fill, &shown, shown.len(), gap)
+        } else {
+            Err(LayoutError::WrongCount { expected: clients.len(), actual: actions.len() })
+        };
+        if let Err(e) = result {
+            log::error!("bad layout of {:?}: {}", to_fill, e);
+        }
+    }
+    actions
+}
+
+fn contains(outer: &Region, inner: &Region) -> bool {
+    let (ox, oy, ow, oh) = outer.values();
+    let (x, y, w, h) = inner.values();
+    x >= ox && y >= oy && x + w <= ox + ow && y + h <= oy + oh
+}
+
+fn overlaps(a: &Region, b: &Region) -> bool {
+    let (ax, ay, aw, ah) = a.values();
+    let (bx, by, bw, bh) = b.values();
+    ax < bx + bw && bx < ax + aw && ay < by + bh && by < ay + ah
+}
+
+/// Grows `r` by `gap` pixels on every side, without leaving `bounds`.
+fn pad(r: &Region, gap: u32, bounds: &Region) -> Region {
+    let (bx, by, bw, bh) = bounds.values();
+    let (x, y, w, h) = r.values();
+    let (left, top) = (x.saturating_sub(gap).max(bx), y.saturating_sub(gap).max(by));
+    let (right, bottom) = ((x + w + gap).min(bx + bw), (y + h + gap).min(by + bh));
+    Region::new(left, top, right - left, bottom - top)
+}
+
+/// The area covered by at least one of the regions.  Layouts only have a handful of windows, so
+/// checking every cell of the grid their edges make is quick enough.
+fn union_area(regions: &[Region]) -> u64 {
+    let (mut xs, mut ys) = (Vec::new(), Vec::new());
+    for (x, y, w, h) in regions.iter().map(|r| r.values()) {
+        xs.extend_from_slice(&[x, x + w]);
+        ys.extend_from_slice(&[y, y + h]);
+    }
+    xs.sort_unstable();
+    xs.dedup();
+    ys.sort_unstable();
+    ys.dedup();
+
+    let mut area = 0;
+    for x in xs.windows(2) {
+        for y in ys.windows(2) {
+            let cell = Region::new(x[0], y[0], x[1] - x[0], y[1] - y[0]);
+            if regions.iter().any(|r| contains(r, &cell)) {
+                area += (x[1] - x[0]) as u64 * (y[1] - y[0]) as u64;
+            }
+        }
+    }
+    area
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen() -> Region {
+        Region::new(0, 0, 100, 100)
+    }
+
+    #[test]
+    fn accepts_a_tiling() {
+        let layout = [Region::new(0, 0, 40, 100), Region::new(40, 0, 60, 100)];
+        assert_eq!(validate(&screen(), &layout, 2, 0), Ok(()));
+    }
+
+    #[test]
+    fn accepts_gaps_up_to_the_gap_size() {
+        let layout = [Region::new(2, 2, 40, 96), Region::new(46, 2, 52, 96)];
+        assert_eq!(validate(&screen(), &layout, 2, 4), Ok(()));
+        assert_eq!(validate(&screen(), &layout, 2, 0), Err(LayoutError::Uncovered { area: 1168 }));
+    }
+
+    #[test]
+    fn rejects_bad_tilings() {
+        let (left, right) = (Region::new(0, 0, 50, 100), Region::new(50, 0, 50, 100));
+        assert!(matches!(validate(&screen(), &[left], 2, 0), Err(LayoutError::WrongCount { .. })));
+        assert_eq!(
+            validate(&screen(), &[left, Region::new(50, 0, 0, 100)], 2, 0),
+            Err(LayoutError::Empty(Region::new(50, 0, 0, 100)))
+        );
+        assert_eq!(
+            validate(&screen(), &[left, Region::new(50, 0, 60, 100)], 2, 0),
+            Err(LayoutError::OutOfBounds(Region::new(50, 0, 60, 100)))
+        );
+        assert_eq!(
+            validate(&screen(), &[left, right, Region::new(40, 40, 20, 20)], 3, 0),
+            Err(LayoutError::Overlapping(left, Region::new(40, 40, 20, 20)))
+        );
+        assert_eq!(validate(&screen(), &[left], 1, 0), Err(LayoutError::Uncovered { area: 5000 }));
+    }
+}
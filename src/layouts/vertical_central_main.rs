@@ -0,0 +1,230 @@
+//! The vertical central main layout is the horizontal central main layout turned on its side.
+//! It has a large central band for your most important window(s) and equally sized bands above
+//! and below it for others, which suits portrait (eg rotated) monitors.
+//!
+//! Windows stay in the main band until they are demoted (see the main_region module), rather
+//! than being pushed out whenever a new window opens.
+//!
+//! On very tall screens the main band is kept from growing much taller than its windows want to
+//! be, with the rest of the space going to the bands above and below it.
+//!
+//! Within each of the three areas, windows are tiled to remain approximately the target aspect
+//! ratio, with ties broken in favor of (by which I mean more space given to) the first window(s)
+//! in the area.
+//!
+
+use penrose::{
+    client::Client,
+    data_types::{Region, ResizeAction},
+    layout::LayoutFunc,
+};
+
+use super::{
+    fair::layout_region_fairly_with,
+    horizontal_central_main::{layout_main_with, split_peripheral, MAX_MAIN_SLACK},
+    main_region, utils, validation, LayoutSettings, Target,
+};
+
+/// Creates and returns a closure that performs layouts.
+pub fn new<S: LayoutSettings>() -> LayoutFunc {
+    |a, _b, c, d, e| S::fit_windows(a, do_vertical_central_main_layout::<S>(a, c, d, e))
+}
+
+fn do_vertical_central_main_layout<S: LayoutSettings>(
+    clients: &[&Client],
+    to_fill: &Region,
+    main_region_window_count: u32,
+    main_region_ratio: f32,
+) -> Vec<ResizeAction> {
+    // Demoted windows can leave the main region short of its full count.
+    let (clients, main_region_window_count) =
+        main_region::main_first(clients, main_region_window_count);
+    let clients = &clients;
+    let targets = &S::targets_for(clients);
+    let (gap, outer_gap) = S::gaps(clients.len());
+    let to_fill = &utils::shrink(to_fill, outer_gap);
+
+    if main_region_window_count >= clients.len() as u32 {
+        do_all_main_layout::<S>(clients, to_fill, targets, gap)
+    } else if main_region_window_count + 1 == clients.len() as u32 {
+        do_two_region_layout::<S>(
+            clients,
+            to_fill,
+            main_region_window_count,
+            main_region_ratio,
+            targets,
+            gap,
+        )
+    } else {
+        do_three_region_layout::<S>(
+            clients,
+            to_fill,
+            main_region_window_count,
+            main_region_ratio,
+            targets,
+            gap,
+        )
+    }
+}
+
+fn region_heights(
+    to_fill: &Region,
+    main_region_ratio: f32,
+    max_main_height: u32,
+    gap: u32,
+) -> (u32, u32) {
+    let (_, _, _, h) = to_fill.values();
+    // Leave room for the gaps either side of the main band.
+    let h = h.saturating_sub(2 * gap);
+    let m = ((h as f32 * main_region_ratio).ceil() as u32).min(max_main_height);
+
+    // The non-main area must be even so it can be cut in half.
+    let m = if (h - m) % 2 == 1 { m.saturating_sub(1) } else { m };
+
+    (m, (h - m) / 2)
+}
+
+/// On very tall (eg rotated 21:9) screens even a modest share of the height can make the main band
+/// absurdly tall, so it is capped at the height of its windows stacked one above the other at
+/// their target aspect ratios.  On ordinary screens there is no cap.
+fn max_main_height(to_fill: &Region, main_targets: &[Target], gap: u32) -> u32 {
+    let (_, _, w, h) = to_fill.values();
+    let gaps = gap * (main_targets.len() as u32).saturating_sub(1);
+    let wanted = main_targets.iter().map(|t| w as f32 / t.aspect_ratio).sum::<f32>() as u32 + gaps;
+    if h as f32 > wanted as f32 * MAX_MAIN_SLACK {
+        wanted
+    } else {
+        u32::MAX
+    }
+}
+
+fn do_all_main_layout<S: LayoutSettings>(
+    clients: &[&Client],
+    to_fill: &Region,
+    targets: &[Target],
+    gap: u32,
+) -> Vec<ResizeAction> {
+    let (x, y, w, h) = to_fill.values();
+    let main_h = h.min(max_main_height(to_fill, targets, gap));
+    let main = Region::new(x, y + (h - main_h) / 2, w, main_h);
+    let layout = layout_main_with::<S>(&main, targets, gap);
+
+    // Only the (possibly capped) main band is tiled, the space above and below is left empty.
+    let actions = clients.iter().zip(layout).map(|(c, r)| (c.id(), Some(r))).collect();
+    validation::debug_checked(clients, &main, gap, actions)
+}
+
+fn do_two_region_layout<S: LayoutSettings>(
+    clients: &[&Client],
+    to_fill: &Region,
+    main_region_window_count: u32,
+    main_region_ratio: f32,
+    targets: &[Target],
+    gap: u32,
+) -> Vec<ResizeAction> {
+    // 2/3rds here to account for the main window also getting the top band.
+    let main_region_ratio = main_region_ratio * 2.0 / 3.0;
+    let (main_targets, secondary_targets) = targets.split_at(main_region_window_count as usize);
+    let (main_h, secondary_h) = region_heights(to_fill, main_region_ratio, u32::MAX, gap);
+    let main_h = (main_h + secondary_h + gap).min(max_main_height(to_fill, main_targets, gap));
+    let (main, secondary) = utils::split_at_height(to_fill, main_h, gap);
+    let main_layout = layout_main_with::<S>(&main, main_targets, gap);
+    let secondary_layout = layout_region_fairly_with::<S>(&secondary, secondary_targets, gap);
+
+    let actions = clients
+        .iter()
+        .zip(main_layout.into_iter().chain(secondary_layout))
+        .map(|(c, r)| (c.id(), Some(r)))
+        .collect();
+    validation::debug_checked(clients, to_fill, gap, actions)
+}
+
+fn do_three_region_layout<S: LayoutSettings>(
+    clients: &[&Client],
+    to_fill: &Region,
+    main_region_window_count: u32,
+    main_region_ratio: f32,
+    targets: &[Target],
+    gap: u32,
+) -> Vec<ResizeAction> {
+    let (main_clients, others) = clients.split_at(main_region_window_count as usize);
+    let (main_targets, other_targets) = targets.split_at(main_region_window_count as usize);
+
+    let max_main_h = max_main_height(to_fill, main_targets, gap);
+    let (main_h, secondary_h) = region_heights(to_fill, main_region_ratio, max_main_h, gap);
+    let (top, remainder) = utils::split_at_height(to_fill, secondary_h, gap);
+    let (main, bottom) = utils::split_at_height(&remainder, main_h, gap);
+    let (top_indices, bottom_indices) = split_peripheral(others.len(), S::FILL_ORDER);
+    let pick = |indices: &[usize]| indices.iter().map(|i| other_targets[*i]).collect::<Vec<_>>();
+
+    let main_layout = layout_main_with::<S>(&main, main_targets, gap);
+    let top_layout = layout_region_fairly_with::<S>(&top, &pick(&top_indices), gap);
+    let bottom_layout = layout_region_fairly_with::<S>(&bottom, &pick(&bottom_indices), gap);
+
+    let actions = main_clients
+        .iter()
+        .zip(main_layout)
+        .chain(top_indices.iter().map(|i| &others[*i]).zip(top_layout))
+        .chain(bottom_indices.iter().map(|i| &others[*i]).zip(bottom_layout))
+        .map(|(c, r)| (c.id(), Some(r)))
+        .collect();
+    validation::debug_checked(clients, to_fill, gap, actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::Widescreen;
+    use proptest::prelude::*;
+
+    struct Gapped;
+
+    impl LayoutSettings for Gapped {
+        const INNER_GAP_PX: u32 = 6;
+        const OUTER_GAP_PX: u32 = 3;
+    }
+
+    fn clients(count: usize) -> Vec<Client> {
+        (0..count)
+            .map(|i| Client::new(i as u32, "hi".to_string(), "there".to_string(), 1, false))
+            .collect()
+    }
+
+    fn regions(actions: Vec<ResizeAction>) -> Vec<Region> {
+        actions.into_iter().filter_map(|(_, r)| r).collect()
+    }
+
+    proptest! {
+        #[test]
+        fn tiles_the_screen(
+            w in 480u32..2160,
+            h in 640u32..7680,
+            count in 1usize..16,
+            main in 0u32..5,
+            ratio in 0.2f32..0.8,
+        ) {
+            let clients = clients(count);
+            let refs: Vec<&Client> = clients.iter().collect();
+            let screen = Region::new(0, 0, w, h);
+
+            let layout =
+                do_vertical_central_main_layout::<Widescreen>(&refs, &screen, main, ratio);
+            // With more than the main windows the whole screen is used, otherwise the main band
+            // may be shortened on tall screens.
+            if count > main as usize {
+                prop_assert_eq!(validation::validate(&screen, &regions(layout), count, 0), Ok(()));
+            } else {
+                prop_assert_eq!(regions(layout).len(), count);
+            }
+
+            let layout = do_vertical_central_main_layout::<Gapped>(&refs, &screen, main, ratio);
+            let inner = utils::shrink(&screen, Gapped::OUTER_GAP_PX);
+            if count > main as usize {
+                let gap = Gapped::INNER_GAP_PX;
+                prop_assert_eq!(validation::validate(&inner, &regions(layout), count, gap), Ok(()));
+            } else {
+                prop_assert_eq!(regions(layout).len(), count);
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 #![forbid(unsafe_code)]
 
-use std::collections::HashMap;
+use std::{collections::HashMap, env, fs, process};
 
 use penrose::{
     core::ring::Selector,
@@ -9,6 +9,7 @@ use penrose::{
     // contrib::actions::focus_or_spawn
     gen_keybindings,
     helpers::index_selectors,
+    layout::Layout,
     run_external,
     run_internal,
     Backward,
@@ -19,16 +20,22 @@ use penrose::{
     XcbConnection,
 };
 
-use thecat::*;
+use simplelog::{LevelFilter, SimpleLogger};
+
+use thecat::{layouts::Widescreen, *};
 
 // TODO LIST //
 // TODO: switch cargo.toml back to pointing upstream once https://github.com/sminez/penrose/pull/75 lands
 // TODO: commit and push
 // TODO: add simple theme support
-// TODO: add some sort of tracking so that windows in the main region don't get pushed out
 // TODO: command line parameters for the style options, perhaps even dynamically adjustable
 
 fn main() -> Result<()> {
+    // Penrose (and the cat's hooks) log what went wrong rather than giving up.
+    if let Err(e) = SimpleLogger::init(LevelFilter::Warn, simplelog::Config::default()) {
+        panic!("unable to set the log handler: {:?}", e);
+    }
+
     let bar_config = bars::AwesomeBarConfiguration::default();
 
     let mut config = Config::default();
@@ -38,10 +45,19 @@ fn main() -> Result<()> {
     config.floating_classes = &["rofi"];
 
     config.hooks.push(Box::new(bars::awesome_bar(Box::new(XCBDraw::new()?), &bar_config)?));
+    config.hooks.push(Box::new(widgets::tab_strip::TabStrip::new(
+        Box::new(XCBDraw::new()?),
+        &Default::default(),
+    )));
+    config.hooks.push(Box::new(layouts::screen_defaults::ScreenDefaults::new(&Default::default())));
+    // Without the windows' own size hints the layouts still honor the ones configured by class.
+    if let Ok(size_hints) = layouts::size_hints::SizeHintsReader::new() {
+        config.hooks.push(Box::new(size_hints));
+    }
 
     // -- layouts --
-    config.layouts =
-        vec![layouts::make_horizontal_central_main_layout(), layouts::make_fair_layout()];
+    config.layouts = layouts::default_layouts().into_iter().map(|l| l.layout).collect();
+    config.layouts.extend(described_layouts());
 
     let key_bindings = gen_keybindings! {
         "M-C-f" => run_external!("firefox");
@@ -68,6 +84,14 @@ fn main() -> Result<()> {
         "M-S-Down" => run_internal!(update_max_main, Less);
         "M-S-Right" => run_internal!(update_main_ratio, More);
         "M-S-Left" => run_internal!(update_main_ratio, Less);
+        "M-m" => Box::new(layouts::main_region::promote);
+        "M-S-m" => Box::new(layouts::main_region::demote);
+        "M-v" => Box::new(layouts::bsp::preselect_top_bottom);
+        "M-b" => Box::new(layouts::bsp::preselect_left_right);
+        "M-e" => Box::new(layouts::bsp::rotate);
+        "M-S-e" => Box::new(layouts::bsp::flip);
+        "M-equal" => Box::new(layouts::bsp::grow);
+        "M-minus" => Box::new(layouts::bsp::shrink);
 
         refmap [ config.ws_range() ] in {
             "M-{}" => focus_workspace [ index_selectors(config.workspaces.len()) ];
@@ -81,3 +105,45 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// Any extra layouts described in ~/.config/thecat/layouts (see the description module), one per
+/// line, eg `[tall] = rows(ratio: main(1), fair)`.
+fn described_layouts() -> Vec<Layout> {
+    let path = match env::var("HOME") {
+        Ok(home) => format!("{}/.config/thecat/layouts", home),
+        Err(_) => return Vec::new(),
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(_) => return Vec::new(),
+    };
+    let described = match layouts::description::parse_config(&text) {
+        Ok(described) => described,
+        Err(e) => {
+            complain(&format!("ignoring {}, {}", path, e));
+            return Vec::new();
+        }
+    };
+
+    // Each layout takes one of the layout functions built at runtime, and there are only so many.
+    let room = layouts::remaining_slots();
+    if described.len() > room {
+        complain(&format!("only the first {} layouts in {} fit, ignoring the rest", room, path));
+    }
+    described
+        .into_iter()
+        .take(room)
+        .map_while(|(symbol, description)| {
+            // Layout symbols have to live as long as the WM does.
+            let symbol: &'static str = Box::leak(symbol.into_boxed_str());
+            layouts::make_described_layout::<Widescreen>(symbol, description).ok()
+        })
+        .collect()
+}
+
+/// Tells the user about a mistake in their config.  Under a display manager nobody sees what the
+/// cat prints, so it goes in the log and pops up as a notification too.
+fn complain(message: &str) {
+    log::error!("{}", message);
+    let _ = process::Command::new("notify-send").arg("thecat").arg(message).spawn();
+}
@@ -13,4 +13,5 @@ pub const DEFAULT_TEXT_STYLE: TextStyle = TextStyle {
 
 pub mod clock;
 pub mod configurations;
+pub mod tab_strip;
 pub mod window_list;
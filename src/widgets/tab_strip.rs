@@ -0,0 +1,174 @@
+//! TabStrip draws the tabs for the tabbed layout: a strip along the top of the screen listing the
+//! windows of the workspace, with the one that has focus (the only one shown) highlighted.
+//!
+//! Unlike the other widgets the strip isn't part of a bar, it has a window of its own on each
+//! screen showing the tabbed layout, in the space the layout leaves for it.
+//!
+
+use std::collections::HashMap;
+
+use penrose::{
+    core::ring::Selector,
+    data_types::{Region, WinId},
+    draw::{Draw, DrawContext, Text, TextStyle, Widget, WinType},
+    hooks::Hook,
+    Result, WindowManager,
+};
+
+use crate::{
+    layouts::TAB_STRIP_HEIGHT_PX,
+    widgets::window_list::{DEFAULT_BACKGROUND_TEXT_STYLE, DEFAULT_FOCUSED_TEXT_STYLE},
+};
+
+/// TabStrip is a hook that draws the tabs for every screen showing the tabbed layout.
+pub struct TabStrip<Ctx> {
+    drw: Box<dyn Draw<Ctx = Ctx>>,
+    symbol: String,
+    highlight: TextStyle<'static>,
+    style: TextStyle<'static>,
+    // The strip window on each screen, by screen index.
+    strips: HashMap<usize, WinId>,
+}
+
+/// All of the settings afforded by TabStrips.
+#[derive(Clone, Debug)]
+pub struct Configuration<'a> {
+    /// The symbol of the tabbed layout, the strip is only shown on screens using it.
+    pub symbol: &'a str,
+    /// The font and color information to use for the focused window's tab.
+    pub highlight: &'a TextStyle<'static>,
+    /// The font and color information to use for the other tabs.
+    pub style: &'a TextStyle<'static>,
+}
+
+impl<Ctx: DrawContext> TabStrip<Ctx> {
+    /// Creates a TabStrip that draws with the given `Draw`.
+    pub fn new(mut drw: Box<dyn Draw<Ctx = Ctx>>, config: &Configuration) -> TabStrip<Ctx> {
+        drw.register_font(config.highlight.font);
+        drw.register_font(config.style.font);
+        TabStrip {
+            drw,
+            symbol: config.symbol.to_string(),
+            highlight: config.highlight.clone(),
+            style: config.style.clone(),
+            strips: HashMap::new(),
+        }
+    }
+
+    fn redraw(
+        &mut self,
+        wm: &WindowManager,
+        workspace_index: usize,
+        screen_index: usize,
+    ) -> Result<()> {
+        let workspace = match wm.workspace(&Selector::Index(workspace_index)) {
+            Some(ws) if ws.layout_symbol() == self.symbol && ws.len() > 0 => ws,
+            _ => {
+                self.hide(screen_index);
+                return Ok(());
+            }
+        };
+        let screen = match wm.screen(&Selector::Index(screen_index)) {
+            Some(screen) => screen.region(true),
+            None => return Ok(()),
+        };
+
+        let (x, y, w, _) = screen.values();
+        let id = match self.strips.get(&screen_index) {
+            Some(id) => *id,
+            None => {
+                let r = Region::new(x, y, w, TAB_STRIP_HEIGHT_PX);
+                let id = self.drw.new_window(
+                    WinType::InputOutput("_NET_WM_WINDOW_TYPE_DOCK"),
+                    r,
+                    false,
+                )?;
+                self.strips.insert(screen_index, id);
+                id
+            }
+        };
+
+        let focused_id = workspace.focused_client();
+        let ids: Vec<WinId> = workspace.iter().copied().collect();
+        let tab_width = w as f64 / ids.len() as f64;
+
+        let mut ctx = self.drw.context_for(id)?;
+        ctx.clear();
+        for (i, id) in ids.iter().enumerate() {
+            let name = wm.client(&Selector::WinId(*id)).map_or("", |c| c.wm_name());
+            let style = if Some(*id) == focused_id { &self.highlight } else { &self.style };
+            ctx.set_x_offset(i as f64 * tab_width);
+            Text::new(name, style, true, false).draw(
+                &mut ctx,
+                screen_index,
+                true,
+                tab_width,
+                TAB_STRIP_HEIGHT_PX as f64,
+            )?;
+        }
+        ctx.flush();
+        self.drw.map_window(id);
+        self.drw.flush(id);
+        Ok(())
+    }
+
+    fn hide(&mut self, screen_index: usize) {
+        if let Some(id) = self.strips.get(&screen_index) {
+            self.drw.unmap_window(*id);
+        }
+    }
+
+    fn redraw_or_complain(
+        &mut self,
+        wm: &WindowManager,
+        workspace_index: usize,
+        screen_index: usize,
+    ) {
+        // Hooks can't fail, so the error goes to the log alongside penrose's own.
+        if let Err(e) = self.redraw(wm, workspace_index, screen_index) {
+            log::error!("unable to draw the tab strip: {}", e);
+        }
+    }
+}
+
+impl<Ctx: DrawContext> Hook for TabStrip<Ctx> {
+    fn client_name_updated(&mut self, wm: &mut WindowManager<'_>, _: WinId, _: &str, _: bool) {
+        let (workspace_index, screen_index) = (wm.active_workspace(), wm.active_screen_index());
+        self.redraw_or_complain(wm, workspace_index, screen_index);
+    }
+
+    fn layout_applied(
+        &mut self,
+        wm: &mut WindowManager<'_>,
+        workspace_index: usize,
+        screen_index: usize,
+    ) {
+        self.redraw_or_complain(wm, workspace_index, screen_index);
+    }
+
+    fn layout_change(
+        &mut self,
+        wm: &mut WindowManager<'_>,
+        workspace_index: usize,
+        screen_index: usize,
+    ) {
+        self.redraw_or_complain(wm, workspace_index, screen_index);
+    }
+
+    fn screens_updated(&mut self, _: &mut WindowManager<'_>, _: &[Region]) {
+        // The screens may have moved, so start again with fresh strips.
+        for (_, id) in self.strips.drain() {
+            self.drw.destroy_window(id);
+        }
+    }
+}
+
+impl<'a> Default for Configuration<'a> {
+    fn default() -> Configuration<'a> {
+        Configuration {
+            symbol: "[tabs]",
+            highlight: &DEFAULT_FOCUSED_TEXT_STYLE,
+            style: &DEFAULT_BACKGROUND_TEXT_STYLE,
+        }
+    }
+}
@@ -1,6 +1,7 @@
 //! WindowList is a widget that lists the windows of the given workspace in order, and highlights
-//! the one that currently has focus.  It also deliniates windows in the main group from the
-//! others.  The overall idea is to make layout behaviors more discoverable.
+//! the one that currently has focus.  It also deliniates windows in the main group (which are
+//! listed first) from the others, and marks windows that have been put in an overflow stack.  The
+//! overall idea is to make layout behaviors more discoverable.
 //!
 
 use std::cmp::min;
@@ -13,7 +14,10 @@ use penrose::{
     Result, WindowManager,
 };
 
-use crate::widgets::DEFAULT_TEXT_STYLE;
+use crate::{
+    layouts::{main_region, overflow},
+    widgets::DEFAULT_TEXT_STYLE,
+};
 
 /// The default style used for whichever window has focus.
 pub const DEFAULT_FOCUSED_TEXT_STYLE: TextStyle =
@@ -34,6 +38,8 @@ pub struct WindowList {
     text_boxes: Vec<(WinId, Text)>,
     separator: String,
     separator_color: Option<Color>,
+    overflow_marker: String,
+    stacked: Vec<WinId>,
     highlight: TextStyle<'static>,
     style: TextStyle<'static>,
 }
@@ -45,6 +51,8 @@ pub struct Configuration<'a> {
     pub separator: &'a str,
     /// The color for the separator.  None means "do not display".
     pub separator_color: Option<Color>,
+    /// This string is put in front of the names of windows in an overflow stack.
+    pub overflow_marker: &'a str,
     /// The font and color information to use for the active window.
     pub highlight: &'a TextStyle<'static>,
     /// The font and color information to use for the text.
@@ -58,6 +66,8 @@ impl WindowList {
             text_boxes: Vec::new(),
             separator: config.separator.to_string(),
             separator_color: config.separator_color,
+            overflow_marker: config.overflow_marker.to_string(),
+            stacked: Vec::new(),
             highlight: config.highlight.clone(),
             style: config.style.clone(),
         }
@@ -67,27 +77,39 @@ impl WindowList {
 impl WindowList {
     fn repopulate(&mut self, wm: &mut WindowManager, workspace_index: usize) {
         self.text_boxes.clear();
+        self.stacked = overflow::stacked(workspace_index);
 
         if let Some(workspace) = wm.workspace(&Selector::Index(workspace_index)) {
             let focused_id = workspace.focused_client();
+            let ids: Vec<WinId> = workspace.iter().copied().collect();
+            let (ids, main_count) =
+                main_region::peek_main_first(workspace_index, &ids, workspace.get_max_main());
 
-            for id in workspace.iter() {
-                if let Some(client) = wm.client(&Selector::WinId(*id)) {
-                    let name = client.wm_name().to_string();
-                    let style = if Some(*id) == focused_id { &self.highlight } else { &self.style };
+            for id in ids {
+                if let Some(client) = wm.client(&Selector::WinId(id)) {
+                    let name = self.display_name(id, client.wm_name());
+                    let style = if Some(id) == focused_id { &self.highlight } else { &self.style };
                     let text = Text::new(name, style, true, false);
-                    self.text_boxes.push((*id, text));
+                    self.text_boxes.push((id, text));
                 }
             }
             // Insert the separator.
             if let Some(color) = self.separator_color {
                 let mut sep = Text::new(self.separator.to_string(), &self.style, false, false);
                 sep.set_fg(color);
-                let i = min(workspace.get_max_main() as usize, self.text_boxes.len());
+                let i = min(main_count, self.text_boxes.len());
                 self.text_boxes.insert(i, (u32::MAX, sep));
             }
         }
     }
+
+    fn display_name(&self, id: WinId, name: &str) -> String {
+        if self.stacked.contains(&id) {
+            format!("{}{}", self.overflow_marker, name)
+        } else {
+            name.to_string()
+        }
+    }
 }
 
 impl Widget for WindowList {
@@ -162,9 +184,10 @@ impl Hook for WindowList {
         name: &str,
         is_root: bool,
     ) {
+        let display_name = self.display_name(id, name);
         for (window_id, text_box) in self.text_boxes.iter_mut() {
             if *window_id == id {
-                text_box.set_text(name);
+                text_box.set_text(display_name.as_str());
             }
             text_box.client_name_updated(wm, id, name, is_root);
         }
@@ -226,6 +249,7 @@ impl<'a> Default for Configuration<'a> {
         Configuration {
             separator: " | ",
             separator_color: None, // Some(Color::from_rgb(0x45, 0x85, 0x88)),
+            overflow_marker: "+",
             highlight: &DEFAULT_FOCUSED_TEXT_STYLE,
             style: &DEFAULT_BACKGROUND_TEXT_STYLE,
         }